@@ -38,9 +38,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         // This configuration should have the lowest overhead, which is a simple atomic load on top
         // of passing the allocation call to the system allocation.
         AllocationRegistry::disable_tracking();
-        unsafe {
-            AllocationRegistry::clear_global_tracker();
-        }
+        AllocationRegistry::clear_global_tracker();
 
         b.iter(|| Vec::<String>::with_capacity(128));
     });
@@ -49,20 +47,16 @@ fn criterion_benchmark(c: &mut Criterion) {
         // This should not change the timing because we always check to see if tracking enabled
         // first, so the tracker being set won't drive any other operations.
         AllocationRegistry::disable_tracking();
-        unsafe {
-            AllocationRegistry::clear_global_tracker();
-        }
-        let _ = AllocationRegistry::set_global_tracker(NoopTracker)
+        AllocationRegistry::clear_global_tracker();
+        AllocationRegistry::set_global_tracker(NoopTracker)
             .expect("no other global tracker should be set");
 
         b.iter(|| Vec::<String>::with_capacity(128));
     });
 
     c.bench_function("enabled/noop tracker", |b| {
-        unsafe {
-            AllocationRegistry::clear_global_tracker();
-        }
-        let _ = AllocationRegistry::set_global_tracker(NoopTracker)
+        AllocationRegistry::clear_global_tracker();
+        AllocationRegistry::set_global_tracker(NoopTracker)
             .expect("no other global tracker should be set");
         AllocationRegistry::enable_tracking();
 
@@ -3,7 +3,7 @@ use tracking_allocator::AllocationGroupToken;
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("group token registration", |b| {
-        b.iter(|| AllocationGroupToken::register());
+        b.iter(AllocationGroupToken::register);
     });
 }
 
@@ -63,7 +63,7 @@ impl AllocationTracker for StdoutTracker {
 fn main() {
     // Create and set our allocation tracker.  Even with the tracker set, we're still not tracking allocations yet.  We
     // need to enable tracking explicitly.
-    let _ = AllocationRegistry::set_global_tracker(StdoutTracker)
+    AllocationRegistry::set_global_tracker(StdoutTracker)
         .expect("no other global tracker should be set yet");
 
     AllocationRegistry::enable_tracking();
@@ -86,8 +86,7 @@ fn main() {
 
     // Now we can finally make some allocations!
     let s = String::from("Hello world!");
-    let mut v = Vec::new();
-    v.push(s);
+    let v = vec![s];
 
     // Drop our "local" group guard.  You can also call `exit` on `AllocationGuard` to transform it back to an
     // `AllocationToken` for further reuse.  Exiting/dropping the guard will update the thread state so that any
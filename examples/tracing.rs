@@ -77,7 +77,7 @@ fn main() {
 
     // Create and set our allocation tracker.  Even with the tracker set, we're still not tracking allocations yet.  We
     // need to enable tracking explicitly.
-    let _ = AllocationRegistry::set_global_tracker(StdoutTracker)
+    AllocationRegistry::set_global_tracker(StdoutTracker)
         .expect("no other global tracker should be set yet");
 
     // Register two allocation groups.  Allocation groups are what allocations are associated with.  and if there is no
@@ -127,8 +127,8 @@ fn main() {
         let handle1 = tokio::spawn(task1);
         let handle2 = tokio::spawn(task2);
 
-        let _ = handle1.await.expect("task1 panicked unexpectedly");
-        let _ = handle2.await.expect("task2 panicked unexpectedly");
+        handle1.await.expect("task1 panicked unexpectedly");
+        handle2.await.expect("task2 panicked unexpectedly");
 
         println!("Done.");
     });
@@ -157,7 +157,7 @@ async fn ping_pong(
     while counter > 0 {
         // We allocate this vector on our side, and send it to the other task to be deallocated.
         let buf: Vec<String> = Vec::with_capacity(buf_size);
-        let _ = tx.send(buf).await.expect("tx send should not fail");
+        tx.send(buf).await.expect("tx send should not fail");
 
         // We receive another buffer from the other, and deallocate it for them.
         let their_buf = rx.recv().await.expect("rx recv should not be empty");
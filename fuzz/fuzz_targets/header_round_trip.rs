@@ -0,0 +1,124 @@
+//! Drives allocations of arbitrary sizes and alignments through the tracking allocator, ensuring that the group ID
+//! written to the trailer on allocation is recovered intact on deallocation, even after the entire object has been
+//! overwritten.
+#![no_main]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{Mutex, Once},
+};
+
+use libfuzzer_sys::fuzz_target;
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// The largest object size we'll try to allocate, which keeps individual runs fast.
+const MAX_OBJECT_SIZE: usize = 1 << 20;
+
+// The largest alignment we'll try to allocate with, as a power of two.
+const MAX_ALIGN_SHIFT: u8 = 16;
+
+#[derive(Debug, PartialEq)]
+struct Event {
+    addr: usize,
+    object_size: usize,
+    wrapped_size: usize,
+    group_id: AllocationGroupId,
+}
+
+static ALLOCATED: Mutex<Option<Event>> = Mutex::new(None);
+static DEALLOCATED: Mutex<Option<Event>> = Mutex::new(None);
+
+struct RoundTripTracker;
+
+impl AllocationTracker for RoundTripTracker {
+    fn allocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if group_id != AllocationGroupId::ROOT {
+            *ALLOCATED.lock().unwrap() = Some(Event {
+                addr,
+                object_size,
+                wrapped_size,
+                group_id,
+            });
+        }
+    }
+
+    fn deallocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if source_group_id != AllocationGroupId::ROOT {
+            *DEALLOCATED.lock().unwrap() = Some(Event {
+                addr,
+                object_size,
+                wrapped_size,
+                group_id: source_group_id,
+            });
+        }
+    }
+}
+
+fuzz_target!(|input: (u32, u8, u8)| {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        AllocationRegistry::set_global_tracker(RoundTripTracker)
+            .expect("no other global tracker should be set");
+        AllocationRegistry::enable_tracking();
+    });
+
+    let (size, align_shift, fill) = input;
+    let size = size as usize % MAX_OBJECT_SIZE;
+    let align = 1usize << (align_shift % (MAX_ALIGN_SHIFT + 1));
+    let layout = match Layout::from_size_align(size.max(1), align) {
+        Ok(layout) => layout,
+        Err(_) => return,
+    };
+
+    let mut token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    let guard = token.enter();
+    let ptr = unsafe { ALLOCATOR.alloc(layout) };
+    drop(guard);
+
+    assert!(!ptr.is_null());
+    assert_eq!(ptr as usize % align, 0, "object pointer is under-aligned");
+
+    let allocated = ALLOCATED
+        .lock()
+        .unwrap()
+        .take()
+        .expect("allocation should have been tracked");
+    assert_eq!(allocated.addr, ptr as usize);
+    assert_eq!(allocated.object_size, layout.size());
+    assert!(allocated.wrapped_size >= layout.size() + std::mem::size_of::<usize>());
+    assert_eq!(allocated.group_id, group_id);
+
+    // Overwrite the entire object, which would clobber the trailer if the trailer offset was miscalculated.
+    unsafe {
+        ptr.write_bytes(fill, layout.size());
+    }
+
+    unsafe { ALLOCATOR.dealloc(ptr, layout) };
+
+    let deallocated = DEALLOCATED
+        .lock()
+        .unwrap()
+        .take()
+        .expect("deallocation should have been tracked");
+    assert_eq!(deallocated, allocated);
+});
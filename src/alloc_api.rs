@@ -0,0 +1,116 @@
+use std::{
+    alloc::{AllocError, Allocator, Layout, System},
+    ptr::NonNull,
+};
+
+use crate::{get_global_tracker, token::try_with_suspended_allocation_group};
+use crate::{AllocationGroupId, AllocationGroupToken};
+
+/// Tracking allocator for individual containers.
+///
+/// Unlike [`Allocator`][crate::Allocator], which tracks every allocation in the process and attributes each one to
+/// whichever allocation group is active on the current thread, `TrackedAlloc` is given to a specific container, such as
+/// via [`Vec::new_in`], and attributes every allocation made through it to a fixed allocation group. Events are passed
+/// to the global tracker, and are subject to tracking being enabled, just like those of the global allocator.
+///
+/// Since the allocation group is fixed, nothing is added to the allocations themselves, so the wrapped size given to
+/// the tracker is always the same as the object size.
+///
+/// The wrapped allocator defaults to [`System`] rather than [`Global`][std::alloc::Global], as allocations made through
+/// the global allocator would be tracked a second time when [`Allocator`][crate::Allocator] is installed as the global
+/// allocator.
+///
+/// ```ignore
+/// #![feature(allocator_api)]
+/// use tracking_allocator::{AllocationGroupToken, TrackedAlloc};
+///
+/// let token = AllocationGroupToken::register().expect("failed to register allocation group");
+/// let mut buf = Vec::new_in(TrackedAlloc::for_group(&token));
+/// buf.push(42);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "allocator-api")))]
+pub struct TrackedAlloc<A = System> {
+    inner: A,
+    group_id: AllocationGroupId,
+}
+
+impl TrackedAlloc<System> {
+    /// Creates a new `TrackedAlloc` that attributes allocations to the given allocation group, using the system
+    /// allocator.
+    #[must_use]
+    pub fn for_group(token: &AllocationGroupToken) -> Self {
+        Self::for_group_in(token, System)
+    }
+}
+
+impl<A> TrackedAlloc<A> {
+    /// Creates a new `TrackedAlloc` that attributes allocations to the given allocation group, using the given
+    /// allocator.
+    #[must_use]
+    pub fn for_group_in(token: &AllocationGroupToken, allocator: A) -> Self {
+        Self {
+            inner: allocator,
+            group_id: token.id(),
+        }
+    }
+
+    /// Gets the ID of the allocation group that allocations are attributed to.
+    #[must_use]
+    pub fn group_id(&self) -> AllocationGroupId {
+        self.group_id
+    }
+}
+
+unsafe impl<A: Allocator> Allocator for TrackedAlloc<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = self.inner.allocate(layout)?;
+
+        if let Some(tracker) = get_global_tracker() {
+            try_with_suspended_allocation_group(|_| {
+                tracker.allocated(
+                    ptr.as_ptr().cast::<u8>() as usize,
+                    layout,
+                    layout.size(),
+                    self.group_id,
+                );
+            });
+        }
+
+        Ok(ptr)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = self.inner.allocate_zeroed(layout)?;
+
+        if let Some(tracker) = get_global_tracker() {
+            try_with_suspended_allocation_group(|_| {
+                tracker.allocated_zeroed(
+                    ptr.as_ptr().cast::<u8>() as usize,
+                    layout,
+                    layout.size(),
+                    self.group_id,
+                );
+            });
+        }
+
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // Deallocate before tracking, just to make sure we're reclaiming memory as soon as possible.
+        self.inner.deallocate(ptr, layout);
+
+        if let Some(tracker) = get_global_tracker() {
+            try_with_suspended_allocation_group(|current_group_id| {
+                tracker.deallocated(
+                    ptr.as_ptr() as usize,
+                    layout,
+                    layout.size(),
+                    self.group_id,
+                    current_group_id,
+                );
+            });
+        }
+    }
+}
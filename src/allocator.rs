@@ -1,7 +1,21 @@
-use std::alloc::{handle_alloc_error, GlobalAlloc, Layout, System};
+use std::{
+    alloc::{handle_alloc_error, GlobalAlloc, Layout, System},
+    cell::Cell,
+    ops::RangeInclusive,
+    ptr,
+};
 
+#[cfg(feature = "global-counters")]
+use crate::counters;
+use crate::events::EventSequence;
+use crate::lifetime::Sequence;
+use crate::observer;
+use crate::side_table::{Entry, SideTable};
 use crate::token::try_with_suspended_allocation_group;
-use crate::{get_global_tracker, AllocationGroupId};
+use crate::{
+    get_global_tracker, global_tracker_installed, panic_suspension_enabled, tracking_enabled,
+    untracked_deallocations_enabled, AllocationGroupId,
+};
 
 /// Tracking allocator implementation.
 ///
@@ -10,13 +24,180 @@ use crate::{get_global_tracker, AllocationGroupId};
 /// library docs for [`GlobalAlloc`].
 pub struct Allocator<A> {
     inner: A,
+    min_tracked_size: usize,
+    max_tracked_size: usize,
+    sampling_rate: usize,
+    track_deallocations: bool,
+    side_table: Option<SideTable>,
+}
+
+thread_local! {
+    /// Number of allocations on this thread that have been skipped since the last sampled allocation.
+    static SKIPPED_SINCE_SAMPLE: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    /// Whether an `Allocator` on this thread is currently calling into the allocator it wraps.
+    static IN_WRAPPED_ALLOCATOR: Cell<bool> = const { Cell::new(false) };
 }
 
 impl<A> Allocator<A> {
     /// Creates a new `Allocator` that wraps another allocator.
+    ///
+    /// Any [`GlobalAlloc`] can be wrapped, such as `Jemalloc` from `tikv-jemallocator` or `MiMalloc` from `mimalloc`.
+    /// Allocations, zeroed allocations, and reallocations are all passed through to the wrapped allocator as such, so
+    /// it keeps any optimizations it has for them, like growing an allocation in place when it still fits in its size
+    /// class. The only difference is that the layouts it sees include the trailing group ID, unless
+    /// [`with_side_table`][Self::with_side_table] is used.
+    ///
+    /// ```ignore
+    /// use tikv_jemallocator::Jemalloc;
+    /// use tracking_allocator::Allocator;
+    ///
+    /// #[global_allocator]
+    /// static GLOBAL: Allocator<Jemalloc> = Allocator::from_allocator(Jemalloc);
+    /// ```
+    ///
+    /// Wrapping another `Allocator` is not supported. It isn't memory-unsafe, as each layer adds its own trailer and
+    /// reads it back, but both layers report every allocation to the same global tracker, so each allocation would be
+    /// tracked twice, once by the outer layer and once by the inner layer with the wrapped size of the outer layer as
+    /// its object size, while paying for two trailers. To have several trackers observe the same allocations, wrap the
+    /// allocator once and install a [`Router`][crate::Router], which passes every event on to any number of trackers.
+    /// In debug builds, a nested `Allocator` aborts the process the first time it's called into while a global tracker
+    /// is installed and tracking is enabled, whether to allocate, reallocate, or deallocate. Nesting isn't detected
+    /// before then, as nothing is tracked twice until then.
     #[must_use]
     pub const fn from_allocator(allocator: A) -> Self {
-        Self { inner: allocator }
+        Self {
+            inner: allocator,
+            min_tracked_size: 0,
+            max_tracked_size: usize::MAX,
+            sampling_rate: 1,
+            track_deallocations: true,
+            side_table: None,
+        }
+    }
+
+    /// Restricts tracking to allocations whose requested size falls within the given range.
+    ///
+    /// Allocations with an object size outside of `range` (both ends inclusive) are still serviced by the wrapped
+    /// allocator, but they are never attributed to an allocation group, and the tracker is not notified when they are
+    /// allocated or deallocated. This makes it possible to isolate a specific population of allocations, such as
+    /// everything between 4KB and 1MB, without paying for tracking everything else.
+    ///
+    /// By default, allocations of all sizes are tracked.
+    #[must_use]
+    pub const fn with_tracked_size_range(mut self, range: RangeInclusive<usize>) -> Self {
+        self.min_tracked_size = *range.start();
+        self.max_tracked_size = *range.end();
+        self
+    }
+
+    /// Only tracks one in every `rate` allocations.
+    ///
+    /// Sampling cuts the overhead of tracking, as allocations that aren't sampled are skipped as early as possible,
+    /// without ever calling the tracker, and their deallocations are skipped just like those of any other untracked
+    /// allocation. Sampling is done per thread, so every thread tracks exactly one in every `rate` of the allocations
+    /// it makes. Trackers can extrapolate totals by scaling what they see by the rate, which is available via
+    /// [`AllocatorConfig::sampling_rate`].
+    ///
+    /// Reallocating a sampled allocation keeps it sampled, so that its deallocation is always seen by the tracker.
+    ///
+    /// Sampling applies on top of [`with_tracked_size_range`][Self::with_tracked_size_range], so only allocations
+    /// within the tracked size range are counted towards the rate. A rate of zero or one tracks every allocation, which
+    /// is the default.
+    #[must_use]
+    pub const fn with_sampling(mut self, rate: usize) -> Self {
+        self.sampling_rate = if rate == 0 { 1 } else { rate };
+        self
+    }
+
+    /// Sets whether or not deallocations are tracked.
+    ///
+    /// When deallocation tracking is disabled, deallocations are passed straight through to the wrapped allocator,
+    /// without reading the group ID of the allocation or calling the tracker at all, which makes deallocating as cheap
+    /// as possible when only allocations are of interest, such as when profiling allocation rates. Reallocations are
+    /// tracked as allocations of the reallocated block. In side table mode, allocations are never recorded in the side
+    /// table, as there's no need to look them up again.
+    ///
+    /// As the tracker never sees anything being deallocated, anything it derives from deallocations, such as the number
+    /// of live bytes in each allocation group, becomes meaningless. The same goes for observer accounting, and for
+    /// untracked deallocations, which are never reported.
+    ///
+    /// By default, deallocations are tracked.
+    #[must_use]
+    pub const fn with_deallocation_tracking(mut self, enabled: bool) -> Self {
+        self.track_deallocations = enabled;
+        self
+    }
+
+    /// Tracks allocations in a side table, rather than in a trailer appended to each allocation.
+    ///
+    /// By default, every allocation is extended with a trailing `usize` (or `u32`, with the `compact-header` feature)
+    /// that holds the ID of the allocation group it was attributed to, followed by a `u64` sequence number with the
+    /// `track-lifetime` feature. This is cheap, but it means that the wrapped allocator sees different layouts than
+    /// were actually requested, which can break allocators that make assumptions about the sizes they're asked for, and
+    /// skews any size class statistics they collect.
+    ///
+    /// In side table mode, layouts are passed to the wrapped allocator unchanged, and the allocation group of each
+    /// tracked allocation is instead recorded in a global, lock-free map of addresses to group IDs, which is looked up
+    /// and cleared when the allocation is deallocated. The tradeoff is that every tracked allocation and deallocation
+    /// must update this shared map, which is slower than reading and writing a trailer, and contends with other threads
+    /// doing the same.
+    ///
+    /// The map can hold at least `capacity` allocations at a time, and is allocated from the wrapped allocator the
+    /// first time an allocation is tracked. Allocations that cannot be recorded because the map is too full are left
+    /// untracked.
+    #[must_use]
+    pub const fn with_side_table(mut self, capacity: usize) -> Self {
+        self.side_table = Some(SideTable::new(capacity));
+        self
+    }
+
+    /// Gets a snapshot of how tracking is currently configured.
+    ///
+    /// This combines the configuration of this allocator with the process-wide tracking state, and is primarily useful
+    /// for diagnostics, such as figuring out why allocation events aren't showing up in a running process.
+    #[must_use]
+    pub fn config(&self) -> AllocatorConfig {
+        AllocatorConfig {
+            tracked_size_range: self.min_tracked_size..=self.max_tracked_size,
+            sampling_rate: self.sampling_rate,
+            deallocation_tracking_enabled: self.track_deallocations,
+            tracking_enabled: tracking_enabled(),
+            tracker_installed: global_tracker_installed(),
+            untracked_deallocations_enabled: untracked_deallocations_enabled(),
+            panic_suspension_enabled: panic_suspension_enabled(),
+            side_table_capacity: self.side_table.as_ref().map(SideTable::capacity),
+        }
+    }
+
+    #[inline(always)]
+    fn should_track_size(&self, object_size: usize) -> bool {
+        object_size >= self.min_tracked_size && object_size <= self.max_tracked_size
+    }
+
+    #[inline(always)]
+    fn should_sample(&self) -> bool {
+        if self.sampling_rate == 1 {
+            return true;
+        }
+
+        // If the thread-local state is unavailable, such as when the thread is being torn down, the allocation is
+        // simply not sampled.
+        SKIPPED_SINCE_SAMPLE
+            .try_with(|skipped| {
+                let next = skipped.get() + 1;
+                if next == self.sampling_rate {
+                    skipped.set(0);
+                    true
+                } else {
+                    skipped.set(next);
+                    false
+                }
+            })
+            .unwrap_or(false)
     }
 }
 
@@ -29,30 +210,168 @@ impl Allocator<System> {
 }
 
 impl<A: GlobalAlloc> Allocator<A> {
+    /// Gets the ID of the allocation group that the given allocation is attributed to.
+    ///
+    /// This looks up the allocation group in the same way as deallocating the allocation would, but without
+    /// deallocating it, which is useful for tools that inspect the heap. `None` is returned if the allocation isn't
+    /// attributed to any allocation group, such as when it was made while tracking was disabled, or when deallocation
+    /// tracking is disabled in side table mode, where allocations are never recorded.
+    ///
+    /// # Safety
+    ///
+    /// `object_ptr` must be a live allocation made by this allocator, and `object_layout` must be the exact layout it
+    /// was allocated (or last reallocated) with, just as when deallocating it.
+    pub unsafe fn group_id_for_ptr(
+        &self,
+        object_ptr: *mut u8,
+        object_layout: Layout,
+    ) -> Option<AllocationGroupId> {
+        let raw_group_id = if let Some(side_table) = &self.side_table {
+            side_table
+                .entries(&self.inner)
+                .and_then(|entries| side_table.find(entries, object_ptr as usize))
+                .map_or(0, Entry::group_id)
+        } else {
+            // SAFETY: See `dealloc`.
+            let (_, offset_to_group_id) = get_wrapped_layout(object_layout)?;
+            decode_group_id(get_group_id_ptr(object_ptr, offset_to_group_id).read())
+        };
+
+        AllocationGroupId::from_raw(raw_group_id)
+    }
+
+    /// Allocates from the wrapped allocator.
+    #[inline(always)]
+    unsafe fn inner_alloc(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+        in_wrapped_allocator(|| {
+            if zeroed {
+                self.inner.alloc_zeroed(layout)
+            } else {
+                self.inner.alloc(layout)
+            }
+        })
+    }
+
+    /// Reallocates with the wrapped allocator.
+    #[inline(always)]
+    unsafe fn inner_realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        in_wrapped_allocator(|| self.inner.realloc(ptr, layout, new_size))
+    }
+
+    /// Deallocates with the wrapped allocator.
+    #[inline(always)]
+    unsafe fn inner_dealloc(&self, ptr: *mut u8, layout: Layout) {
+        in_wrapped_allocator(|| self.inner.dealloc(ptr, layout));
+    }
+
+    /// Allocates the wrapped allocation for the given object layout.
+    ///
+    /// If the wrapped layout cannot be represented, `None` is returned, and the allocation should be failed.
     unsafe fn get_wrapped_allocation(
         &self,
         object_layout: Layout,
-    ) -> (*mut usize, *mut u8, Layout) {
+        zeroed: bool,
+    ) -> Option<(*mut RawTrailer, *mut u8, Layout)> {
         // Allocate our wrapped layout and make sure the allocation succeeded.
-        let (actual_layout, offset_to_object) = get_wrapped_layout(object_layout);
-        let actual_ptr = self.inner.alloc(actual_layout);
+        let (actual_layout, offset_to_group_id) = get_wrapped_layout(object_layout)?;
+        let actual_ptr = self.inner_alloc(actual_layout, zeroed);
         if actual_ptr.is_null() {
-            handle_alloc_error(actual_layout);
+            Self::report_alloc_error(object_layout, actual_layout);
         }
 
+        #[cfg(feature = "global-counters")]
+        counters::record_allocation(object_layout.size());
+
         // Zero out the group ID field to make sure it's in the `None` state.
-        //
-        // SAFETY: We know that `actual_ptr` is at least aligned enough for casting it to `*mut usize` as the layout for
-        // the allocation backing this pointer ensures the first field in the layout is `usize.
-        #[allow(clippy::cast_ptr_alignment)]
-        let group_id_ptr = actual_ptr.cast::<usize>();
+        let group_id_ptr = get_group_id_ptr(actual_ptr, offset_to_group_id);
         group_id_ptr.write(0);
 
-        // SAFETY: If the allocation succeeded and `actual_ptr` is valid, then it must be valid to advance by
-        // `offset_to_object` as it would land within the allocation.
-        let object_ptr = actual_ptr.wrapping_add(offset_to_object);
+        // The object lives at the very start of the wrapped allocation, with the group ID trailing it.
+        Some((group_id_ptr, actual_ptr, actual_layout))
+    }
+}
+
+/// Snapshot of the tracking configuration of an [`Allocator`].
+///
+/// Returned by [`Allocator::config`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
+pub struct AllocatorConfig {
+    /// The range of object sizes, in bytes, that are tracked.
+    pub tracked_size_range: RangeInclusive<usize>,
+
+    /// The sampling rate, where one in every `sampling_rate` allocations is tracked.
+    pub sampling_rate: usize,
+
+    /// Whether or not deallocations are tracked.
+    pub deallocation_tracking_enabled: bool,
+
+    /// Whether or not tracking is enabled.
+    pub tracking_enabled: bool,
 
-        (group_id_ptr, object_ptr, actual_layout)
+    /// Whether or not a global tracker has been installed.
+    pub tracker_installed: bool,
+
+    /// Whether or not deallocations of untracked allocations are passed to the tracker.
+    pub untracked_deallocations_enabled: bool,
+
+    /// Whether or not allocations made while the current thread is panicking are left untracked.
+    pub panic_suspension_enabled: bool,
+
+    /// The capacity of the side table, if allocations are tracked in a side table rather than in a trailer.
+    pub side_table_capacity: Option<usize>,
+}
+
+impl<A> Allocator<A> {
+    /// Lets the tracker know that an allocation is about to be made.
+    #[inline(always)]
+    fn notify_allocating(&self, object_layout: Layout) {
+        if !self.should_track_size(object_layout.size())
+            || observer::is_observer_allocation()
+            || (panic_suspension_enabled() && std::thread::panicking())
+        {
+            return;
+        }
+
+        if let Some(tracker) = get_global_tracker() {
+            try_with_suspended_allocation_group(
+                #[inline(always)]
+                |group_id| tracker.allocating(object_layout, group_id),
+            );
+        }
+    }
+
+    /// Lets the tracker know that an allocation has failed, before handing over to the allocation error handler.
+    #[cold]
+    fn report_alloc_error(object_layout: Layout, layout: Layout) -> ! {
+        if let Some(tracker) = get_global_tracker() {
+            try_with_suspended_allocation_group(|group_id| {
+                tracker.allocation_failed(object_layout, group_id);
+            });
+        }
+
+        handle_alloc_error(layout)
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+impl<A> Allocator<A> {
+    /// Checks whether the tracker wants the given allocation to fail.
+    #[inline(always)]
+    fn should_fail_allocation(object_layout: Layout) -> bool {
+        let mut should_fail = false;
+
+        if let Some(tracker) = get_global_tracker() {
+            try_with_suspended_allocation_group(
+                #[inline(always)]
+                |group_id| {
+                    should_fail = tracker.should_fail_allocation(object_layout.size(), group_id);
+                },
+            );
+        }
+
+        should_fail
     }
 }
 
@@ -62,87 +381,840 @@ impl Default for Allocator<System> {
     }
 }
 
-unsafe impl<A: GlobalAlloc> GlobalAlloc for Allocator<A> {
-    #[track_caller]
-    unsafe fn alloc(&self, object_layout: Layout) -> *mut u8 {
-        let (group_id_ptr, object_ptr, wrapped_layout) = self.get_wrapped_allocation(object_layout);
-        let object_addr = object_ptr as usize;
+impl<A> Allocator<A> {
+    /// Tracks an allocation that was just made, writing the group ID it belongs to into its trailer.
+    ///
+    /// The trailer must already be zeroed, so that the allocation is treated as untracked if it's never attributed to
+    /// an allocation group. If `zeroed` is `true`, the allocation is reported as a zeroed allocation.
+    #[inline(always)]
+    unsafe fn track_allocation(
+        &self,
+        group_id_ptr: *mut RawTrailer,
+        object_addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        zeroed: bool,
+    ) {
         let object_size = object_layout.size();
-        let wrapped_size = wrapped_layout.size();
+
+        // Allocations outside of the tracked size range keep the zeroed trailer, so their deallocation is skipped just
+        // the same as any other untracked allocation.
+        if !self.should_track_size(object_size) {
+            return;
+        }
+
+        // Allocations made by the tracker itself are attributed to the observer group, when enabled, rather than being
+        // passed back to the tracker.
+        if observer::is_observer_allocation() {
+            group_id_ptr.write(encode_group_id(
+                AllocationGroupId::OBSERVER.as_usize().get(),
+            ));
+            observer::record_allocation(object_size);
+            return;
+        }
+
+        // Allocations made while unwinding are left untracked, when enabled, so that they don't skew the statistics of
+        // whichever allocation group happened to be active when the panic occurred.
+        if panic_suspension_enabled() && std::thread::panicking() {
+            return;
+        }
 
         if let Some(tracker) = get_global_tracker() {
             try_with_suspended_allocation_group(
                 #[inline(always)]
                 |group_id| {
-                    // We only set the group ID in the wrapper header if we're tracking an allocation, because when it
+                    // Allocations that aren't sampled keep the zeroed trailer, so they're skipped on deallocation as
+                    // well. We only sample here, so that allocations made by the tracker itself don't count towards the
+                    // sampling rate.
+                    if !self.should_sample() {
+                        return;
+                    }
+
+                    // We only set the group ID in the wrapper trailer if we're tracking an allocation, because when it
                     // comes back to us during deallocation, we want to skip doing any checks at all if it's already
                     // zero.
                     //
                     // If we never track the allocation, tracking the deallocation will only produce incorrect numbers,
                     // and that includes even if we just used the rule of "always attribute allocations to the root
                     // allocation group by default".
-                    group_id_ptr.write(group_id.as_usize().get());
-                    tracker.allocated(object_addr, object_size, wrapped_size, group_id);
+                    group_id_ptr.write(encode_group_id(group_id.as_usize().get()));
+                    write_sequence(group_id_ptr, Sequence::next());
+
+                    #[cfg(feature = "global-counters")]
+                    if group_id == AllocationGroupId::ROOT {
+                        counters::record_root_allocation();
+                    }
+
+                    #[cfg(feature = "event-sequence")]
+                    let sequenced = (EventSequence::next(), group_id);
+
+                    if zeroed {
+                        tracker.allocated_zeroed(
+                            object_addr,
+                            object_layout,
+                            wrapped_size,
+                            group_id,
+                        );
+                    } else {
+                        tracker.allocated(object_addr, object_layout, wrapped_size, group_id);
+                    }
+
+                    #[cfg(feature = "event-sequence")]
+                    tracker.allocated_with_sequence(
+                        object_addr,
+                        object_size,
+                        sequenced.1,
+                        sequenced.0.get(),
+                    );
                 },
             );
         }
+    }
+
+    /// Tracks a deallocation, given the raw group ID and sequence number that were read from the trailer of the
+    /// allocation, and the event sequence number that was taken before the allocation was handed back.
+    #[inline(always)]
+    #[cfg_attr(
+        not(all(feature = "track-lifetime", feature = "event-sequence")),
+        allow(unused_variables)
+    )]
+    fn track_deallocation(
+        &self,
+        raw_group_id: usize,
+        allocated_at: Sequence,
+        event: EventSequence,
+        object_addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+    ) {
+        if !self.track_deallocations {
+            return;
+        }
+
+        if raw_group_id == AllocationGroupId::OBSERVER.as_usize().get() {
+            observer::record_deallocation(object_layout.size());
+            return;
+        }
+
+        if let Some(tracker) = get_global_tracker() {
+            // Untracked allocations are skipped entirely unless the user has explicitly asked to see them.
+            let source_group_id = match AllocationGroupId::from_raw(raw_group_id) {
+                Some(source_group_id) => source_group_id,
+                None if untracked_deallocations_enabled() => AllocationGroupId::UNTRACKED,
+                None => return,
+            };
+
+            try_with_suspended_allocation_group(
+                #[inline(always)]
+                |current_group_id| {
+                    #[cfg(feature = "track-lifetime")]
+                    let aged_group_id = (source_group_id != AllocationGroupId::UNTRACKED)
+                        .then_some(source_group_id);
+                    #[cfg(feature = "event-sequence")]
+                    let sequenced_group_ids = (source_group_id, current_group_id);
+
+                    tracker.deallocated(
+                        object_addr,
+                        object_layout,
+                        wrapped_size,
+                        source_group_id,
+                        current_group_id,
+                    );
+
+                    #[cfg(feature = "track-lifetime")]
+                    if let Some(source_group_id) = aged_group_id {
+                        tracker.deallocated_with_age(
+                            object_addr,
+                            object_layout.size(),
+                            source_group_id,
+                            allocated_at.age(),
+                        );
+                    }
+
+                    #[cfg(feature = "event-sequence")]
+                    tracker.deallocated_with_sequence(
+                        object_addr,
+                        object_layout.size(),
+                        sequenced_group_ids.0,
+                        sequenced_group_ids.1,
+                        event.get(),
+                    );
+                },
+            );
+        }
+    }
+
+    /// Tracks a reallocation as a single event, if both the original block and the reallocated block are tracked.
+    ///
+    /// Object layouts and wrapped sizes are given as `(old, new)` pairs. If `false` is returned, nothing was tracked,
+    /// and the reallocation must be tracked as a separate deallocation and allocation instead. Otherwise, the
+    /// reallocated block keeps the sequence number of the original allocation, as it's still the same allocation as far
+    /// as the tracker is concerned.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn track_reallocation(
+        &self,
+        raw_group_id: usize,
+        allocated_at: Sequence,
+        group_id_ptr: *mut RawTrailer,
+        old_addr: usize,
+        new_addr: usize,
+        object_layouts: (Layout, Layout),
+        wrapped_sizes: (usize, usize),
+    ) -> bool {
+        if !self.track_deallocations {
+            return false;
+        }
+
+        let source_group_id = match AllocationGroupId::from_raw(raw_group_id) {
+            Some(source_group_id) if source_group_id != AllocationGroupId::OBSERVER => {
+                source_group_id
+            }
+            _ => return false,
+        };
+
+        if !self.should_track_size(object_layouts.1.size())
+            || observer::is_observer_allocation()
+            || (panic_suspension_enabled() && std::thread::panicking())
+        {
+            return false;
+        }
+
+        if let Some(tracker) = get_global_tracker() {
+            // Zero the trailer first, so that the reallocated block is untracked if we end up not tracking it.
+            group_id_ptr.write(0);
+
+            try_with_suspended_allocation_group(
+                #[inline(always)]
+                |current_group_id| {
+                    group_id_ptr.write(encode_group_id(current_group_id.as_usize().get()));
+                    write_sequence(group_id_ptr, allocated_at);
+
+                    #[cfg(feature = "event-sequence")]
+                    tracker.reallocated_with_sequence(
+                        old_addr,
+                        new_addr,
+                        object_layouts.0.size(),
+                        object_layouts.1.size(),
+                        source_group_id,
+                        current_group_id,
+                        EventSequence::next().get(),
+                    );
+
+                    tracker.reallocated(
+                        old_addr,
+                        new_addr,
+                        object_layouts.0,
+                        object_layouts.1.size(),
+                        wrapped_sizes.0,
+                        wrapped_sizes.1,
+                        source_group_id,
+                        current_group_id,
+                    );
+                },
+            );
+
+            return true;
+        }
+
+        false
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for Allocator<A> {
+    #[track_caller]
+    unsafe fn alloc(&self, object_layout: Layout) -> *mut u8 {
+        #[cfg(debug_assertions)]
+        abort_if_nested();
+
+        self.notify_allocating(object_layout);
+
+        #[cfg(feature = "fault-injection")]
+        if Self::should_fail_allocation(object_layout) {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(side_table) = &self.side_table {
+            return self.side_table_alloc(side_table, object_layout, false);
+        }
+
+        let Some((group_id_ptr, object_ptr, wrapped_layout)) =
+            self.get_wrapped_allocation(object_layout, false)
+        else {
+            return ptr::null_mut();
+        };
+        self.track_allocation(
+            group_id_ptr,
+            object_ptr as usize,
+            object_layout,
+            wrapped_layout.size(),
+            false,
+        );
+
+        object_ptr
+    }
+
+    #[track_caller]
+    unsafe fn alloc_zeroed(&self, object_layout: Layout) -> *mut u8 {
+        #[cfg(debug_assertions)]
+        abort_if_nested();
+
+        self.notify_allocating(object_layout);
+
+        #[cfg(feature = "fault-injection")]
+        if Self::should_fail_allocation(object_layout) {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(side_table) = &self.side_table {
+            return self.side_table_alloc(side_table, object_layout, true);
+        }
+
+        // The wrapped allocator zeroes the entire wrapped allocation, trailer included, which leaves the trailer in the
+        // same untracked state that `alloc` starts from.
+        let Some((group_id_ptr, object_ptr, wrapped_layout)) =
+            self.get_wrapped_allocation(object_layout, true)
+        else {
+            return ptr::null_mut();
+        };
+        self.track_allocation(
+            group_id_ptr,
+            object_ptr as usize,
+            object_layout,
+            wrapped_layout.size(),
+            true,
+        );
 
         object_ptr
     }
 
     #[track_caller]
     unsafe fn dealloc(&self, object_ptr: *mut u8, object_layout: Layout) {
-        // Regenerate the wrapped layout so we know where we have to look, as the pointer we've given relates to the
-        // requested layout, not the wrapped layout that was actually allocated.
-        let (wrapped_layout, offset_to_object) = get_wrapped_layout(object_layout);
+        #[cfg(debug_assertions)]
+        abort_if_nested();
 
-        // SAFETY: We only ever return pointers to the actual requested object layout, not our wrapped layout. Since
-        // global allocators cannot be changed at runtime, we know that if we're here, then the given pointer, and the
-        // allocation it refers to, was allocated by us. Thus, since we wrap _all_ allocations, we know that this object
-        // pointer can be safely subtracted by `offset_to_object` to get back to the group ID field in our wrapper.
-        let actual_ptr = object_ptr.wrapping_sub(offset_to_object);
+        #[cfg(feature = "global-counters")]
+        counters::record_deallocation();
 
-        // SAFETY: We know that `actual_ptr` is at least aligned enough for casting it to `*mut usize` as the layout for
-        // the allocation backing this pointer ensures the first field in the layout is `usize.
-        #[allow(clippy::cast_ptr_alignment)]
-        let raw_group_id = actual_ptr.cast::<usize>().read();
+        if let Some(side_table) = &self.side_table {
+            return self.side_table_dealloc(side_table, object_ptr, object_layout);
+        }
+
+        // Regenerate the wrapped layout so we know where we have to look, as the layout we've been given is the
+        // requested layout, not the wrapped layout that was actually allocated. This can't fail, as we were able to
+        // wrap the same layout when allocating.
+        let (wrapped_layout, offset_to_group_id) =
+            get_wrapped_layout(object_layout).expect("bug: allocated layout could not be wrapped");
+
+        if !self.track_deallocations {
+            self.inner_dealloc(object_ptr, wrapped_layout);
+            return;
+        }
+
+        // SAFETY: We only ever return pointers to the start of our wrapped allocations, which is where the object
+        // lives. Since global allocators cannot be changed at runtime, we know that if we're here, then the given
+        // pointer, and the allocation it refers to, was allocated by us. Thus, since we wrap _all_ allocations, we know
+        // that the group ID field trails the object at `offset_to_group_id`.
+        let group_id_ptr = get_group_id_ptr(object_ptr, offset_to_group_id);
+        let raw_group_id = decode_group_id(group_id_ptr.read());
+        let allocated_at = read_sequence(group_id_ptr);
+
+        // The event sequence number is taken before deallocating, so that it comes before that of any allocation that
+        // reuses the memory.
+        let event = EventSequence::next();
 
         // Deallocate before tracking, just to make sure we're reclaiming memory as soon as possible.
-        self.inner.dealloc(actual_ptr, wrapped_layout);
+        self.inner_dealloc(object_ptr, wrapped_layout);
 
-        let object_addr = object_ptr as usize;
-        let object_size = object_layout.size();
-        let wrapped_size = wrapped_layout.size();
+        self.track_deallocation(
+            raw_group_id,
+            allocated_at,
+            event,
+            object_ptr as usize,
+            object_layout,
+            wrapped_layout.size(),
+        );
+    }
 
-        if let Some(tracker) = get_global_tracker() {
-            if let Some(source_group_id) = AllocationGroupId::from_raw(raw_group_id) {
-                try_with_suspended_allocation_group(
-                    #[inline(always)]
-                    |current_group_id| {
-                        tracker.deallocated(
-                            object_addr,
-                            object_size,
-                            wrapped_size,
-                            source_group_id,
-                            current_group_id,
-                        );
-                    },
-                );
+    #[track_caller]
+    unsafe fn realloc(
+        &self,
+        object_ptr: *mut u8,
+        object_layout: Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        // SAFETY: The caller guarantees that `new_size`, when rounded up to the alignment of `object_layout`, does not
+        // overflow, which is exactly what is required to construct the new layout.
+        let new_object_layout = Layout::from_size_align_unchecked(new_size, object_layout.align());
+
+        #[cfg(debug_assertions)]
+        abort_if_nested();
+
+        self.notify_allocating(new_object_layout);
+
+        #[cfg(feature = "fault-injection")]
+        if Self::should_fail_allocation(new_object_layout) {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(side_table) = &self.side_table {
+            return self.side_table_realloc(
+                side_table,
+                object_ptr,
+                object_layout,
+                new_object_layout,
+            );
+        }
+
+        // The group ID trails the object, so its offset changes along with the size of the object. We read it before
+        // reallocating, as it may not survive being shrunk, and always write it again afterwards.
+        let (wrapped_layout, offset_to_group_id) =
+            get_wrapped_layout(object_layout).expect("bug: allocated layout could not be wrapped");
+        let Some((new_wrapped_layout, new_offset_to_group_id)) =
+            get_wrapped_layout(new_object_layout)
+        else {
+            return ptr::null_mut();
+        };
+
+        // SAFETY: See `dealloc`.
+        let old_group_id_ptr = get_group_id_ptr(object_ptr, offset_to_group_id);
+        let raw_group_id = decode_group_id(old_group_id_ptr.read());
+        let allocated_at = read_sequence(old_group_id_ptr);
+
+        // On failure, the original allocation is left untouched, and so is its trailer.
+        let new_object_ptr =
+            self.inner_realloc(object_ptr, wrapped_layout, new_wrapped_layout.size());
+        if new_object_ptr.is_null() {
+            return new_object_ptr;
+        }
+
+        #[cfg(feature = "global-counters")]
+        counters::record_reallocation(new_size);
+
+        let group_id_ptr = get_group_id_ptr(new_object_ptr, new_offset_to_group_id);
+
+        // When both the original block and the reallocated block are tracked, we report a single reallocation.
+        // Otherwise, the group ID that was read from the original allocation is used to track it as having been
+        // deallocated, and we then zero the trailer and track the reallocated block as a new allocation.
+        if !self.track_reallocation(
+            raw_group_id,
+            allocated_at,
+            group_id_ptr,
+            object_ptr as usize,
+            new_object_ptr as usize,
+            (object_layout, new_object_layout),
+            (wrapped_layout.size(), new_wrapped_layout.size()),
+        ) {
+            self.track_deallocation(
+                raw_group_id,
+                allocated_at,
+                EventSequence::next(),
+                object_ptr as usize,
+                object_layout,
+                wrapped_layout.size(),
+            );
+
+            group_id_ptr.write(0);
+            self.track_allocation(
+                group_id_ptr,
+                new_object_ptr as usize,
+                new_object_layout,
+                new_wrapped_layout.size(),
+                false,
+            );
+        }
+
+        new_object_ptr
+    }
+}
+
+impl<A: GlobalAlloc> Allocator<A> {
+    /// Allocates and tracks an allocation in side table mode.
+    unsafe fn side_table_alloc(
+        &self,
+        side_table: &SideTable,
+        object_layout: Layout,
+        zeroed: bool,
+    ) -> *mut u8 {
+        let object_ptr = self.inner_alloc(object_layout, zeroed);
+        if object_ptr.is_null() {
+            Self::report_alloc_error(object_layout, object_layout);
+        }
+
+        #[cfg(feature = "global-counters")]
+        counters::record_allocation(object_layout.size());
+
+        self.side_table_track_allocation(side_table, object_ptr as usize, object_layout, zeroed);
+
+        object_ptr
+    }
+
+    /// Tracks an allocation in side table mode, recording its group ID in the side table if it was tracked.
+    unsafe fn side_table_track_allocation(
+        &self,
+        side_table: &SideTable,
+        object_addr: usize,
+        object_layout: Layout,
+        zeroed: bool,
+    ) {
+        // Avoid touching the side table at all when there's nothing that could track the allocation.
+        if get_global_tracker().is_none() && !observer::is_observer_allocation() {
+            return;
+        }
+
+        // Without deallocation tracking, the allocation never needs to be looked up again, so we only track it.
+        if !self.track_deallocations {
+            let mut trailer = Trailer::default();
+            self.track_allocation(
+                ptr::addr_of_mut!(trailer).cast::<RawTrailer>(),
+                object_addr,
+                object_layout,
+                object_layout.size(),
+                zeroed,
+            );
+            return;
+        }
+
+        let Some(entry) = side_table
+            .entries(&self.inner)
+            .and_then(|entries| side_table.claim(entries, object_addr))
+        else {
+            return;
+        };
+
+        let mut trailer = Trailer::default();
+        self.track_allocation(
+            ptr::addr_of_mut!(trailer).cast::<RawTrailer>(),
+            object_addr,
+            object_layout,
+            object_layout.size(),
+            zeroed,
+        );
+
+        if trailer.group_id == 0 {
+            entry.release();
+        } else {
+            entry.set_group_id(decode_group_id(trailer.group_id));
+            entry.set_sequence(trailer.sequence);
+        }
+    }
+
+    /// Deallocates and tracks an allocation in side table mode.
+    unsafe fn side_table_dealloc(
+        &self,
+        side_table: &SideTable,
+        object_ptr: *mut u8,
+        object_layout: Layout,
+    ) {
+        if !self.track_deallocations {
+            self.inner_dealloc(object_ptr, object_layout);
+            return;
+        }
+
+        let (raw_group_id, allocated_at) = side_table
+            .entries(&self.inner)
+            .map_or((0, Sequence::default()), |entries| {
+                side_table.take(entries, object_ptr as usize)
+            });
+
+        // The event sequence number is taken before deallocating, so that it comes before that of any allocation that
+        // reuses the memory.
+        let event = EventSequence::next();
+
+        // Deallocate before tracking, just to make sure we're reclaiming memory as soon as possible.
+        self.inner_dealloc(object_ptr, object_layout);
+
+        self.track_deallocation(
+            raw_group_id,
+            allocated_at,
+            event,
+            object_ptr as usize,
+            object_layout,
+            object_layout.size(),
+        );
+    }
+
+    /// Reallocates and tracks an allocation in side table mode.
+    unsafe fn side_table_realloc(
+        &self,
+        side_table: &SideTable,
+        object_ptr: *mut u8,
+        object_layout: Layout,
+        new_object_layout: Layout,
+    ) -> *mut u8 {
+        // Without deallocation tracking, nothing is ever recorded in the side table, so there's nothing to look up.
+        let entries = if self.track_deallocations {
+            side_table.entries(&self.inner)
+        } else {
+            None
+        };
+
+        // The original allocation is taken out of the side table before reallocating, just like when deallocating, as
+        // once the block has moved, its address may be handed out to, and claimed by, another allocation right away. On
+        // failure, the original allocation is left untouched, so we put it back.
+        let (raw_group_id, allocated_at) = entries.map_or((0, Sequence::default()), |entries| {
+            side_table.take(entries, object_ptr as usize)
+        });
+
+        let new_object_ptr =
+            self.inner_realloc(object_ptr, object_layout, new_object_layout.size());
+        if new_object_ptr.is_null() {
+            if let Some(entries) = entries {
+                side_table.restore(entries, object_ptr as usize, raw_group_id, allocated_at);
             }
+            return new_object_ptr;
         }
+
+        #[cfg(feature = "global-counters")]
+        counters::record_reallocation(new_object_layout.size());
+
+        // Without deallocation tracking, the reallocated block is simply tracked as a new allocation.
+        if !self.track_deallocations {
+            self.side_table_track_allocation(
+                side_table,
+                new_object_ptr as usize,
+                new_object_layout,
+                false,
+            );
+            return new_object_ptr;
+        }
+
+        let new_entry =
+            entries.and_then(|entries| side_table.claim(entries, new_object_ptr as usize));
+        let Some(new_entry) = new_entry else {
+            // The reallocated block can't be recorded, so it's left untracked, and the original allocation is simply
+            // tracked as having been deallocated.
+            self.track_deallocation(
+                raw_group_id,
+                allocated_at,
+                EventSequence::next(),
+                object_ptr as usize,
+                object_layout,
+                object_layout.size(),
+            );
+            return new_object_ptr;
+        };
+
+        // Just like with the trailer, we try to track a single reallocation, and fall back to tracking a deallocation
+        // followed by an allocation.
+        let mut new_trailer = Trailer::default();
+        if !self.track_reallocation(
+            raw_group_id,
+            allocated_at,
+            ptr::addr_of_mut!(new_trailer).cast::<RawTrailer>(),
+            object_ptr as usize,
+            new_object_ptr as usize,
+            (object_layout, new_object_layout),
+            (object_layout.size(), new_object_layout.size()),
+        ) {
+            self.track_deallocation(
+                raw_group_id,
+                allocated_at,
+                EventSequence::next(),
+                object_ptr as usize,
+                object_layout,
+                object_layout.size(),
+            );
+
+            new_trailer = Trailer::default();
+            self.track_allocation(
+                ptr::addr_of_mut!(new_trailer).cast::<RawTrailer>(),
+                new_object_ptr as usize,
+                new_object_layout,
+                new_object_layout.size(),
+                false,
+            );
+        }
+
+        if new_trailer.group_id == 0 {
+            new_entry.release();
+        } else {
+            new_entry.set_group_id(decode_group_id(new_trailer.group_id));
+            new_entry.set_sequence(new_trailer.sequence);
+        }
+
+        new_object_ptr
     }
 }
 
-fn get_wrapped_layout(object_layout: Layout) -> (Layout, usize) {
-    static HEADER_LAYOUT: Layout = Layout::new::<usize>();
+/// Runs `f`, which calls into the wrapped allocator.
+///
+/// In debug builds, the current thread is marked as being inside the wrapped allocator for the duration of the call, so
+/// that a nested `Allocator` can detect that it's being wrapped, whether it's allocating, reallocating, or
+/// deallocating.
+#[inline(always)]
+fn in_wrapped_allocator<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    #[cfg(debug_assertions)]
+    let was_in_wrapped_allocator = IN_WRAPPED_ALLOCATOR.with(|flag| flag.replace(true));
+
+    let result = f();
+
+    #[cfg(debug_assertions)]
+    IN_WRAPPED_ALLOCATOR.with(|flag| flag.set(was_in_wrapped_allocator));
 
-    // We generate a new allocation layout that gives us a location to store the active allocation group ID ahead
-    // of the requested allocation, which lets us always attempt to retrieve it on the deallocation path. We'll
-    // always set this to zero, and conditionally update it to the actual allocation group ID if tracking is enabled.
-    let (actual_layout, offset_to_object) = HEADER_LAYOUT
-        .extend(object_layout)
-        .expect("wrapping requested layout resulted in overflow");
+    result
+}
+
+/// Aborts the process if this `Allocator` is wrapped by another `Allocator`, and would track the allocation.
+///
+/// Nesting is harmless while nothing is being tracked, so this only aborts once a tracker is installed and tracking is
+/// enabled: until then, nested `Allocator`s go undetected. Panicking isn't an option, as allocators must not unwind,
+/// and the message is written directly to standard error, as formatting it could allocate.
+#[cfg(debug_assertions)]
+#[inline(always)]
+fn abort_if_nested() {
+    if IN_WRAPPED_ALLOCATOR.with(Cell::get) && get_global_tracker().is_some() {
+        let _ = std::io::Write::write_all(
+            &mut std::io::stderr(),
+            b"tracking-allocator: an `Allocator` cannot wrap another `Allocator`, as every allocation would be tracked \
+              twice; wrap the allocator once, and use a `Router` to pass events on to several trackers\n",
+        );
+        std::process::abort();
+    }
+}
+
+/// Gets the wrapped layout for the given object layout, along with the offset of the group ID field within it.
+///
+/// If the object layout is so large that the wrapped layout would overflow, `None` is returned.
+fn get_wrapped_layout(object_layout: Layout) -> Option<(Layout, usize)> {
+    static TRAILER_LAYOUT: Layout = Layout::new::<Trailer>();
+
+    // We generate a new allocation layout that gives us a location to store the active allocation group ID after the
+    // requested allocation, which lets us always attempt to retrieve it on the deallocation path. We'll always set this
+    // to zero, and conditionally update it to the actual allocation group ID if tracking is enabled.
+    //
+    // Storing the group ID after the object, rather than before it, keeps the object at the start of the allocation, so
+    // highly-aligned objects don't need a full alignment stride of padding just to fit the group ID in front of them:
+    // the group ID only needs to fit in whatever is left over after the object, rounded up to its own alignment.
+    //
+    // Zero-sized layouts never make it here: calling a global allocator with a zero-sized layout is undefined behavior
+    // on the part of the caller, which is why the standard library hands out dangling pointers for zero-sized types and
+    // empty collections without ever calling the allocator. Even if it did happen, the wrapped layout would simply be
+    // the trailer on its own, which is a valid, non-zero-sized allocation, and the trailer would be read back from the
+    // same offset on deallocation.
+    //
+    // For object layouts that are close to the maximum size, adding the trailer can overflow, in which case we fail the
+    // allocation, just as the wrapped allocator would for a layout that it can't satisfy, rather than panicking from
+    // within the allocator.
+    let (actual_layout, offset_to_group_id) = object_layout.extend(TRAILER_LAYOUT).ok()?;
     let actual_layout = actual_layout.pad_to_align();
 
-    (actual_layout, offset_to_object)
+    Some((actual_layout, offset_to_group_id))
+}
+
+/// Gets the number of bytes added to an allocation by tracking it.
+///
+/// This is the difference between the wrapped size and the object size given to the tracker, which covers both the
+/// group ID stored alongside the object and any padding needed to keep the wrapped allocation aligned. For highly
+/// aligned objects, the padding can be much larger than the group ID itself. Allocations tracked in side table mode, or
+/// via `TrackedAlloc`, are passed through unchanged, and so have no overhead.
+#[must_use]
+pub const fn tracking_overhead(object_size: usize, wrapped_size: usize) -> usize {
+    wrapped_size.saturating_sub(object_size)
+}
+
+/// Gets a pointer to the group ID field of a wrapped allocation.
+///
+/// # Safety
+///
+/// `actual_ptr` must point to a wrapped allocation whose group ID field is at `offset_to_group_id`.
+#[inline(always)]
+unsafe fn get_group_id_ptr(actual_ptr: *mut u8, offset_to_group_id: usize) -> *mut RawTrailer {
+    // SAFETY: The caller guarantees that the group ID field is within the allocation, and the wrapped layout ensures
+    // that it is aligned for `RawTrailer`.
+    #[allow(clippy::cast_ptr_alignment)]
+    actual_ptr.add(offset_to_group_id).cast::<RawTrailer>()
+}
+
+/// The group ID field that trails every wrapped allocation.
+///
+/// With the `compact-header` feature, this is a `u32` rather than a `usize`, which halves the overhead of tracking on
+/// 64-bit targets, unless the `track-lifetime` feature is also enabled, as the sequence number that it adds to the
+/// trailer is eight-byte aligned. Zero always means that the allocation is untracked.
+///
+/// As the trailer comes after the object, the object always keeps its own alignment, however small the trailer is. The
+/// trailer only shrinks the padding needed for objects whose alignment is lower than that of a `usize`: objects aligned
+/// to eight bytes or more still round the wrapped allocation up to their own alignment, and so see no savings.
+#[cfg(not(feature = "compact-header"))]
+type RawTrailer = usize;
+#[cfg(feature = "compact-header")]
+type RawTrailer = u32;
+
+/// The full trailer of a wrapped allocation.
+///
+/// The group ID always comes first, so a pointer to the trailer is also a pointer to its group ID field. With the
+/// `track-lifetime` feature, the trailer also holds the sequence number of the allocation, which widens it by eight
+/// bytes. Without it, the sequence number is zero-sized, and the trailer is exactly the group ID field.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct Trailer {
+    group_id: RawTrailer,
+    sequence: Sequence,
+}
+
+/// Reads the sequence number from the trailer of an allocation.
+///
+/// # Safety
+///
+/// `group_id_ptr` must point to the group ID field of a full trailer.
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn read_sequence(group_id_ptr: *mut RawTrailer) -> Sequence {
+    // SAFETY: The wrapped layout is extended by the full trailer, and the side table hands out a full trailer on the
+    // stack, so the group ID field is always aligned for the trailer as a whole.
+    ptr::addr_of!((*group_id_ptr.cast::<Trailer>()).sequence).read()
+}
+
+/// Writes the sequence number into the trailer of an allocation.
+///
+/// # Safety
+///
+/// `group_id_ptr` must point to the group ID field of a full trailer.
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn write_sequence(group_id_ptr: *mut RawTrailer, sequence: Sequence) {
+    ptr::addr_of_mut!((*group_id_ptr.cast::<Trailer>()).sequence).write(sequence);
+}
+
+/// Converts a raw group ID into the value stored in the trailer of an allocation.
+#[cfg(not(feature = "compact-header"))]
+#[inline(always)]
+fn encode_group_id(raw_group_id: usize) -> RawTrailer {
+    raw_group_id
+}
+
+/// Converts the value stored in the trailer of an allocation back into a raw group ID.
+#[cfg(not(feature = "compact-header"))]
+#[inline(always)]
+fn decode_group_id(trailer: RawTrailer) -> usize {
+    trailer
+}
+
+/// Converts a raw group ID into the value stored in the trailer of an allocation.
+///
+/// Registered group IDs always fit in a `u32`, as registration fails once they no longer would. The observer group ID
+/// doesn't, so it's stored as `u32::MAX` instead, which is never handed out to a registered allocation group.
+#[cfg(feature = "compact-header")]
+#[inline(always)]
+#[allow(clippy::cast_possible_truncation)]
+fn encode_group_id(raw_group_id: usize) -> RawTrailer {
+    if raw_group_id == AllocationGroupId::OBSERVER.as_usize().get() {
+        u32::MAX
+    } else {
+        raw_group_id as u32
+    }
+}
+
+/// Converts the value stored in the trailer of an allocation back into a raw group ID.
+#[cfg(feature = "compact-header")]
+#[inline(always)]
+fn decode_group_id(trailer: RawTrailer) -> usize {
+    if trailer == u32::MAX {
+        AllocationGroupId::OBSERVER.as_usize().get()
+    } else {
+        trailer as usize
+    }
 }
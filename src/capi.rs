@@ -0,0 +1,56 @@
+use std::convert::TryFrom;
+
+use crate::token::{innermost_entered_group, UnsafeAllocationGroupToken};
+use crate::{AllocationGroupId, AllocationGroupToken};
+
+/// Registers a new allocation group, returning its group ID.
+///
+/// This is the C equivalent of [`AllocationGroupToken::register`]. If the number of registered allocation groups
+/// exceeds the limit, zero is returned, which is never a valid group ID.
+///
+/// ```c
+/// uint64_t tracking_alloc_group_acquire(void);
+/// ```
+#[no_mangle]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub extern "C" fn tracking_alloc_group_acquire() -> u64 {
+    AllocationGroupToken::register().map_or(0, |token| token.id().as_usize().get() as u64)
+}
+
+/// Enters the allocation group with the given group ID, marking it as the active allocation group on this thread.
+///
+/// As there are no guards in C, every call to this function must be paired with a call to
+/// [`tracking_alloc_group_exit`], made on the same thread: allocation groups are tracked per thread, so exiting on a
+/// different thread than the group was entered on leaves both threads attributing allocations to the wrong allocation
+/// group. Allocation groups must also be exited in the reverse order that they were entered in.
+///
+/// Group IDs that don't fit in a `usize`, as well as zero, are ignored.
+///
+/// ```c
+/// void tracking_alloc_group_enter(uint64_t id);
+/// ```
+#[no_mangle]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub extern "C" fn tracking_alloc_group_enter(id: u64) {
+    if let Some(id) = usize::try_from(id)
+        .ok()
+        .and_then(AllocationGroupId::from_raw)
+    {
+        UnsafeAllocationGroupToken::new(id).enter();
+    }
+}
+
+/// Exits the most recently entered allocation group on this thread, restoring the previously active allocation group.
+///
+/// If no allocation group has been entered on this thread, this does nothing.
+///
+/// ```c
+/// void tracking_alloc_group_exit(void);
+/// ```
+#[no_mangle]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub extern "C" fn tracking_alloc_group_exit() {
+    if let Some(id) = innermost_entered_group() {
+        UnsafeAllocationGroupToken::new(id).exit();
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TOTAL_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static TOTAL_DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static TOTAL_BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static ROOT_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Process-wide allocation counters.
+///
+/// These are maintained by [`Allocator`][crate::Allocator] for every allocation it services, regardless of whether
+/// tracking is enabled, whether a tracker is installed, or which allocation group is active, which makes them useful
+/// for quick sanity checks that don't warrant a custom tracker. Byte counts are based on the requested object size, and
+/// don't include any space added by the allocator for tracking.
+///
+/// A reallocation is counted as a deallocation of the original allocation, followed by an allocation of the new size.
+///
+/// Comparing [`root_allocations`][Self::root_allocations] against [`total_allocations`][Self::total_allocations] gives
+/// a rough idea of how much of the allocation activity in a process happens outside of any allocation group, which is
+/// useful when deciding where to add allocation groups.
+///
+/// Returned by [`global_allocation_counters`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "global-counters")))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GlobalCounters {
+    /// Number of allocations made.
+    pub total_allocations: usize,
+
+    /// Number of deallocations made.
+    pub total_deallocations: usize,
+
+    /// Total bytes allocated.
+    pub total_bytes_allocated: usize,
+
+    /// Number of allocations attributed to the root allocation group, as they were made while no allocation group was
+    /// active.
+    ///
+    /// Unlike the other counters, this is only counted for allocations that are passed to the tracker, and so only
+    /// while tracking is enabled, and only for allocations that pass any size range or sampling configured on the
+    /// allocator.
+    pub root_allocations: usize,
+}
+
+/// Gets a snapshot of the process-wide allocation counters.
+///
+/// The counters are updated independently of one another, so a snapshot taken while other threads are allocating may
+/// not be perfectly consistent.
+#[must_use]
+#[cfg_attr(docsrs, doc(cfg(feature = "global-counters")))]
+pub fn global_allocation_counters() -> GlobalCounters {
+    GlobalCounters {
+        total_allocations: TOTAL_ALLOCATIONS.load(Ordering::Relaxed),
+        total_deallocations: TOTAL_DEALLOCATIONS.load(Ordering::Relaxed),
+        total_bytes_allocated: TOTAL_BYTES_ALLOCATED.load(Ordering::Relaxed),
+        root_allocations: ROOT_ALLOCATIONS.load(Ordering::Relaxed),
+    }
+}
+
+#[inline(always)]
+pub(crate) fn record_allocation(object_size: usize) {
+    TOTAL_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    TOTAL_BYTES_ALLOCATED.fetch_add(object_size, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn record_root_allocation() {
+    ROOT_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn record_deallocation() {
+    TOTAL_DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn record_reallocation(new_object_size: usize) {
+    record_deallocation();
+    record_allocation(new_object_size);
+}
@@ -0,0 +1,38 @@
+#[cfg(feature = "event-sequence")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The sequence number to hand out to the next allocation event.
+#[cfg(feature = "event-sequence")]
+static NEXT_EVENT: AtomicU64 = AtomicU64::new(0);
+
+/// The position of an allocation event in the total order of allocation events across all threads.
+///
+/// With the `event-sequence` feature, allocations, deallocations, and reallocations all take their sequence number from
+/// the same counter, so that the events of every thread can be merged back into a single, consistent order. Without it,
+/// this is zero-sized, and does nothing.
+#[derive(Clone, Copy)]
+pub(crate) struct EventSequence {
+    #[cfg(feature = "event-sequence")]
+    value: u64,
+}
+
+impl EventSequence {
+    /// Takes the next sequence number.
+    ///
+    /// Allocations must take their sequence number after the wrapped allocator has handed out the memory, and
+    /// deallocations before they give it back, so that an address that is freed and then reused always has its
+    /// deallocation ordered before its reallocation.
+    #[inline(always)]
+    pub fn next() -> Self {
+        Self {
+            #[cfg(feature = "event-sequence")]
+            value: NEXT_EVENT.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Gets the raw sequence number.
+    #[cfg(feature = "event-sequence")]
+    pub fn get(self) -> u64 {
+        self.value
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::AllocationGroupToken;
+
+/// Instruments a future so that its allocations are attributed to the given allocation group.
+///
+/// The allocation group is entered every time the future is polled, and exited again as soon as the poll returns, no
+/// matter if the future is ready or not. Work done by other tasks in between polls, on whichever thread the future
+/// happens to be polled on, is thus never attributed to the allocation group. This is the equivalent of
+/// [`AllocationGroupToken::attach_to_span`] for applications that don't use `tracing`, and carries the allocation group
+/// across `.await` points, as well as across threads when the future is spawned on a multi-threaded runtime.
+///
+/// ```
+/// use tracking_allocator::{instrument_alloc, AllocationGroupToken};
+///
+/// # async fn handle_request() {}
+/// let token = AllocationGroupToken::register().expect("failed to register allocation group");
+/// let future = instrument_alloc(token, async {
+///     // Allocations made while polling this future are attributed to the allocation group.
+///     handle_request().await;
+/// });
+/// # drop(future);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-compat")))]
+pub fn instrument_alloc<F>(token: AllocationGroupToken, future: F) -> Instrumented<F>
+where
+    F: Future,
+{
+    Instrumented { token, future }
+}
+
+/// Future that enters an allocation group whenever it is polled.
+///
+/// Created by [`instrument_alloc`].
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-compat")))]
+pub struct Instrumented<F> {
+    token: AllocationGroupToken,
+    future: F,
+}
+
+impl<F> Instrumented<F> {
+    /// Consumes this future, returning the allocation group token and the inner future.
+    pub fn into_inner(self) -> (AllocationGroupToken, F) {
+        (self.token, self.future)
+    }
+}
+
+impl<F> Future for Instrumented<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `self` while pinned, and `token` is never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        let _guard = this.token.enter();
+        future.poll(cx)
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, PoisonError, RwLock,
+    },
+};
+
+use crate::{token::with_suspended_allocation_group, AllocationGroupId, AllocationTracker};
+
+/// Number of shards that the per-group histograms are spread across.
+const SHARDS: usize = 16;
+
+/// Number of buckets in a histogram: one for every power of two that fits in a `usize`, plus one for sizes above the
+/// largest power of two.
+const BUCKETS: usize = usize::BITS as usize + 1;
+
+struct Buckets([AtomicUsize; BUCKETS]);
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self(std::array::from_fn(|_| AtomicUsize::new(0)))
+    }
+}
+
+type Shard = RwLock<HashMap<AllocationGroupId, Buckets>>;
+
+/// Histogram of allocation sizes for a single allocation group.
+///
+/// Returned by [`HistogramTracker::snapshot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GroupHistogram {
+    /// The allocation group this histogram is for.
+    pub group_id: AllocationGroupId,
+
+    /// Number of allocations made by the allocation group, bucketed by object size.
+    ///
+    /// Bucket `i` counts allocations whose size is greater than `2^(i-1)` and at most `2^i`, with the first bucket also
+    /// counting zero-sized allocations, and the last bucket counting allocations larger than the largest power of two
+    /// that fits in a `usize`. Use [`bucket_for_size`][GroupHistogram::bucket_for_size] to find the bucket for a size.
+    pub buckets: Vec<usize>,
+}
+
+impl GroupHistogram {
+    /// Gets the index of the bucket that allocations of the given size are counted in.
+    #[must_use]
+    pub fn bucket_for_size(size: usize) -> usize {
+        size.checked_next_power_of_two()
+            .map_or(BUCKETS - 1, |size| size.trailing_zeros() as usize)
+    }
+
+    /// Gets the total number of allocations in the histogram.
+    #[must_use]
+    pub fn allocations(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+}
+
+/// Tracker that keeps a histogram of allocation sizes for every allocation group.
+///
+/// Allocations are bucketed by object size, as requested by the caller, into power-of-two buckets, which is useful for
+/// analyzing fragmentation and picking size classes. Only allocations are counted: deallocations don't change the
+/// histogram.
+///
+/// Like [`StatsTracker`][crate::StatsTracker], `HistogramTracker` is cheaply cloneable, with all clones sharing the
+/// same histograms, so that a clone can be installed as the global tracker while another is kept around to take
+/// snapshots from.
+#[derive(Clone, Default)]
+pub struct HistogramTracker {
+    shards: Arc<[Shard; SHARDS]>,
+}
+
+impl HistogramTracker {
+    /// Creates a new `HistogramTracker` with empty histograms.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets a snapshot of the histogram of every allocation group that has been seen so far, ordered by group ID.
+    ///
+    /// Buckets are read individually, so a histogram may be slightly inconsistent if allocations are happening
+    /// concurrently.
+    ///
+    /// Any allocations made while taking the snapshot are not tracked.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<GroupHistogram> {
+        // As with `StatsTracker`, tracking the allocations made while holding a shard's lock could need to take that
+        // same lock for writing, so they're left untracked.
+        with_suspended_allocation_group(|| {
+            let mut snapshot = Vec::new();
+            for shard in self.shards.iter() {
+                let shard = shard.read().unwrap_or_else(PoisonError::into_inner);
+                snapshot.extend(shard.iter().map(|(group_id, buckets)| {
+                    GroupHistogram {
+                        group_id: *group_id,
+                        buckets: buckets
+                            .0
+                            .iter()
+                            .map(|bucket| bucket.load(Ordering::Relaxed))
+                            .collect(),
+                    }
+                }));
+            }
+
+            snapshot.sort_by_key(|a| a.group_id);
+            snapshot
+        })
+    }
+
+    fn with_buckets<F>(&self, group_id: AllocationGroupId, f: F)
+    where
+        F: FnOnce(&Buckets),
+    {
+        let shard = &self.shards[group_id.as_usize().get() % SHARDS];
+
+        if let Some(buckets) = shard
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&group_id)
+        {
+            f(buckets);
+            return;
+        }
+
+        f(shard
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(group_id)
+            .or_default());
+    }
+}
+
+impl AllocationTracker for HistogramTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        let bucket = GroupHistogram::bucket_for_size(object_size);
+        self.with_buckets(group_id, |buckets| {
+            buckets.0[bucket].fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
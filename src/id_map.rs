@@ -0,0 +1,147 @@
+use std::iter;
+
+use crate::AllocationGroupId;
+
+/// A map keyed by allocation group ID, backed by a vector.
+///
+/// Registered group IDs are small, dense integers, so rather than hashing them, `IdVecMap` stores each value directly
+/// at the index given by its group ID, making lookups a bounds check and an array access. The
+/// [`UNTRACKED`][AllocationGroupId::UNTRACKED] and [`OBSERVER`][AllocationGroupId::OBSERVER] group IDs, which sit at
+/// the very top of the ID space, are stored separately, so they don't cost any more space than any other group ID.
+///
+/// As the backing vector grows to fit the largest group ID inserted so far, `IdVecMap` is only meant for group IDs
+/// handed out by [`AllocationGroupToken::register`][crate::AllocationGroupToken::register], and not for arbitrary IDs
+/// created via [`AllocationGroupId::from_raw`].
+///
+/// Like any other map, `IdVecMap` allocates as it grows, so trackers that use it should guard it accordingly, the same
+/// as they would for a `HashMap`.
+#[derive(Clone, Debug)]
+pub struct IdVecMap<V> {
+    values: Vec<Option<V>>,
+    observer: Option<V>,
+    untracked: Option<V>,
+    len: usize,
+}
+
+impl<V> IdVecMap<V> {
+    /// Creates an empty `IdVecMap`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            observer: None,
+            untracked: None,
+            len: 0,
+        }
+    }
+
+    /// Gets the number of entries in the map.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets a reference to the value for the given group ID, if any.
+    #[must_use]
+    pub fn get(&self, id: AllocationGroupId) -> Option<&V> {
+        match id {
+            AllocationGroupId::UNTRACKED => self.untracked.as_ref(),
+            AllocationGroupId::OBSERVER => self.observer.as_ref(),
+            id => self.values.get(index(id)).and_then(Option::as_ref),
+        }
+    }
+
+    /// Gets a mutable reference to the value for the given group ID, if any.
+    pub fn get_mut(&mut self, id: AllocationGroupId) -> Option<&mut V> {
+        self.slot_mut(id).and_then(Option::as_mut)
+    }
+
+    /// Inserts a value for the given group ID, returning the previous value, if any.
+    pub fn insert(&mut self, id: AllocationGroupId, value: V) -> Option<V> {
+        let previous = self.slot(id).replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Removes the value for the given group ID, returning it, if any.
+    pub fn remove(&mut self, id: AllocationGroupId) -> Option<V> {
+        let previous = self.slot_mut(id).and_then(Option::take);
+        if previous.is_some() {
+            self.len -= 1;
+        }
+        previous
+    }
+
+    /// Gets a mutable reference to the value for the given group ID, inserting the value returned by `f` first if
+    /// there is none.
+    pub fn get_or_insert_with<F>(&mut self, id: AllocationGroupId, f: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        if self.get(id).is_none() {
+            self.len += 1;
+        }
+        self.slot(id).get_or_insert_with(f)
+    }
+
+    /// Iterates over the entries of the map, in ascending order of group ID.
+    pub fn iter(&self) -> impl Iterator<Item = (AllocationGroupId, &V)> + '_ {
+        let registered = self
+            .values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| AllocationGroupId::from_raw(i + 1).zip(value.as_ref()));
+        let observer = iter::once(AllocationGroupId::OBSERVER).zip(self.observer.as_ref());
+        let untracked = iter::once(AllocationGroupId::UNTRACKED).zip(self.untracked.as_ref());
+        registered.chain(observer).chain(untracked)
+    }
+
+    /// Removes all entries from the map, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.values.iter_mut().for_each(|value| *value = None);
+        self.observer = None;
+        self.untracked = None;
+        self.len = 0;
+    }
+
+    fn slot(&mut self, id: AllocationGroupId) -> &mut Option<V> {
+        match id {
+            AllocationGroupId::UNTRACKED => &mut self.untracked,
+            AllocationGroupId::OBSERVER => &mut self.observer,
+            id => grow_to(&mut self.values, index(id)),
+        }
+    }
+
+    fn slot_mut(&mut self, id: AllocationGroupId) -> Option<&mut Option<V>> {
+        match id {
+            AllocationGroupId::UNTRACKED => Some(&mut self.untracked),
+            AllocationGroupId::OBSERVER => Some(&mut self.observer),
+            id => self.values.get_mut(index(id)),
+        }
+    }
+}
+
+impl<V> Default for IdVecMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn index(id: AllocationGroupId) -> usize {
+    id.as_usize().get() - 1
+}
+
+fn grow_to<V>(values: &mut Vec<Option<V>>, index: usize) -> &mut Option<V> {
+    if index >= values.len() {
+        values.resize_with(index + 1, || None);
+    }
+    &mut values[index]
+}
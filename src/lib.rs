@@ -20,6 +20,24 @@
 //! Additionally, tracking can be enabled and disabled at runtime, allowing you to make the choice of when to incur the
 //! performance overhead of tracking.
 //!
+//! ## tracking overhead
+//!
+//! In order to attribute deallocations to the allocation group that made the allocation, every allocation is wrapped
+//! with a small trailer that holds the group ID. This is reflected in the two sizes given to [`AllocationTracker`]: the
+//! object size, which is the size that was requested, and the wrapped size, which is the size of the allocation that
+//! was actually made. The difference between the two is the tracking overhead for that allocation, which is the trailer
+//! itself plus any padding required to keep the allocation correctly aligned.
+//!
+//! As the overhead is paid per allocation, and not per byte, it compounds for data structures made up of many small
+//! allocations: a `Vec<Box<T>>` pays it once for the buffer of the vector, and once more for every boxed element. When
+//! analyzing overhead, trackers should keep the number of allocations and the sum of `wrapped_size - object_size`
+//! separately per allocation group, rather than only comparing total wrapped bytes against total object bytes, so that
+//! groups with many small allocations can be told apart from groups with a few large, highly-aligned allocations.
+//!
+//! When the wrapped allocator must see the layouts that were actually requested, [`Allocator::with_side_table`] can be
+//! used to record group IDs in a global map instead of a trailer. In that mode, the wrapped size is always equal to the
+//! object size, but every tracked allocation and deallocation pays for updating the shared map.
+//!
 //! ## examples
 //!
 //! Two main examples are provided: `stdout` and `tracing`.  Both examples demonstrate how to effectively to use the
@@ -31,20 +49,43 @@
 //!
 //! [global_alloc]: std::alloc::GlobalAlloc
 #![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(feature = "allocator-api", feature(allocator_api))]
 #![deny(missing_docs)]
 #![deny(clippy::pedantic)]
 #![allow(clippy::inline_always)]
 #![allow(clippy::module_name_repetitions)]
 use std::{
-    error, fmt,
+    alloc::Layout,
+    borrow::Cow,
+    error, fmt, ptr,
     sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicPtr, Ordering},
         Arc,
     },
 };
 
+#[cfg(feature = "allocator-api")]
+mod alloc_api;
 mod allocator;
+#[cfg(feature = "capi")]
+mod capi;
+#[cfg(feature = "global-counters")]
+mod counters;
+mod events;
+#[cfg(feature = "tokio-compat")]
+mod future;
+mod histogram;
+mod id_map;
+mod lifetime;
+mod metadata;
+mod noop;
+mod observer;
+mod pool;
+mod router;
+mod side_table;
 mod stack;
+mod stats;
+mod threshold;
 mod token;
 #[cfg(feature = "tracing-compat")]
 mod tracing;
@@ -52,22 +93,48 @@ mod util;
 
 use token::with_suspended_allocation_group;
 
-pub use crate::allocator::Allocator;
-pub use crate::token::{AllocationGroupId, AllocationGroupToken, AllocationGuard};
+#[cfg(feature = "allocator-api")]
+pub use crate::alloc_api::TrackedAlloc;
+pub use crate::allocator::{tracking_overhead, Allocator, AllocatorConfig};
+#[cfg(feature = "capi")]
+pub use crate::capi::{
+    tracking_alloc_group_acquire, tracking_alloc_group_enter, tracking_alloc_group_exit,
+};
+#[cfg(feature = "global-counters")]
+pub use crate::counters::{global_allocation_counters, GlobalCounters};
+#[cfg(feature = "tokio-compat")]
+pub use crate::future::{instrument_alloc, Instrumented};
+pub use crate::histogram::{GroupHistogram, HistogramTracker};
+pub use crate::id_map::IdVecMap;
+pub use crate::noop::NoopTracker;
+pub use crate::observer::ObserverUsage;
+pub use crate::pool::{AllocationGroupPool, PooledToken};
+pub use crate::router::{RouteFilter, Router};
+pub use crate::stats::{diff_live_bytes, GroupStats, StatsTracker};
+pub use crate::threshold::ThresholdTracker;
+pub use crate::token::{
+    active_group_stack, current_allocation_group, spawn_in_group, suspend_tracking,
+    AllocationGroupId, AllocationGroupToken, AllocationGuard, InvalidGroupIdError,
+    SuspendedAllocationGuard,
+};
 #[cfg(feature = "tracing-compat")]
-pub use crate::tracing::AllocationLayer;
+pub use crate::tracing::{AllocationLayer, TracingTracker};
 
 /// Whether or not allocations should be tracked.
 static TRACKING_ENABLED: AtomicBool = AtomicBool::new(false);
 
+/// Whether or not deallocations of untracked allocations should be passed to the tracker.
+static UNTRACKED_DEALLOCATIONS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether or not allocations made while the current thread is panicking should be left untracked.
+static PANIC_SUSPENSION_ENABLED: AtomicBool = AtomicBool::new(false);
+
 // The global tracker.  This is called for all allocations, passing through the information to
 // whichever implementation is currently set.
-static mut GLOBAL_TRACKER: Option<Tracker> = None;
-static GLOBAL_INIT: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
-
-const UNINITIALIZED: usize = 0;
-const INITIALIZING: usize = 1;
-const INITIALIZED: usize = 2;
+//
+// Trackers are leaked when they're installed, and never freed, so that a tracker which is replaced or cleared can still
+// be used by any thread that loaded it right before that happened.
+static GLOBAL_TRACKER: AtomicPtr<Tracker> = AtomicPtr::new(ptr::null_mut());
 
 /// Tracks allocations and deallocations.
 pub trait AllocationTracker {
@@ -81,7 +148,12 @@ pub trait AllocationTracker {
     /// As the allocator will customize the layout to include the group ID which owns an allocation, we provide two
     /// sizes: the object size and the wrapped size. The object size is the original layout of the allocation, and is
     /// valid against the given object address. The wrapped size is the true size of the underlying allocation that is
-    /// made, and represents the actual memory usage for the given allocation.
+    /// made, and represents the actual memory usage for the given allocation. It includes both the group ID and any
+    /// padding needed to keep the allocation aligned, so [`tracking_overhead`] gives the full cost of tracking it.
+    ///
+    /// Allocations made while the current thread is panicking are attributed to the active allocation group, just like
+    /// any other allocation, unless panic suspension has been enabled via
+    /// [`AllocationRegistry::enable_panic_suspension`].
     fn allocated(
         &self,
         addr: usize,
@@ -90,12 +162,63 @@ pub trait AllocationTracker {
         group_id: AllocationGroupId,
     );
 
+    /// Tracks when an allocation has occurred, along with the layout of the allocation.
+    ///
+    /// This is what the allocator calls for every allocation, and the default implementation calls
+    /// [`allocated`][Self::allocated] with the size of `object_layout`. Trackers only need to implement this if they
+    /// need more than the size of the allocation, such as its alignment, which is useful for diagnosing the overhead of
+    /// highly-aligned allocations: when there is no room left for the trailer holding the group ID after the object,
+    /// the trailer is padded out to the alignment of the object, so the difference between the wrapped size and the
+    /// object size grows with the alignment.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::allocated_with_layout` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    fn allocated_with_layout(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        self.allocated(addr, object_layout.size(), wrapped_size, group_id);
+    }
+
+    /// Tracks when a zeroed allocation has occurred.
+    ///
+    /// This is called instead of [`allocated_with_layout`][Self::allocated_with_layout] for allocations made via
+    /// [`GlobalAlloc::alloc_zeroed`][std::alloc::GlobalAlloc::alloc_zeroed], such as by `vec![0; n]`, which makes it
+    /// possible to tell calloc-style allocations apart from regular ones. The default implementation calls
+    /// [`allocated_with_layout`][Self::allocated_with_layout], so trackers that don't care about the distinction don't
+    /// need to implement it.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::allocated_zeroed` are ignored,
+    /// so implementors can allocate/deallocate without risk of reentrancy bugs.
+    fn allocated_zeroed(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        report_allocated(self, addr, object_layout, wrapped_size, group_id);
+    }
+
     /// Tracks when a deallocation has occurred.
     ///
     /// `source_group_id` contains the group ID where the given allocation originated from, while `current_group_id` is
     /// the current group ID, and as such, these values may differ depending on how values have had their ownership
     /// transferred.
     ///
+    /// When a value is dropped, the deallocations for any of its storage carry the group ID that allocated that storage
+    /// as `source_group_id`, and the group ID active wherever the drop happens as `current_group_id`. Any allocations
+    /// made while dropping, such as by a destructor that allocates, are attributed to the group active where the drop
+    /// happens. Trackers are free to charge deallocations to either group: charging `source_group_id` keeps per-group
+    /// live bytes balanced, while charging `current_group_id` attributes the work of dropping to the dropping context.
+    ///
+    /// Deallocations of allocations that were never tracked are only passed to the tracker if untracked deallocations
+    /// have been enabled via [`AllocationRegistry::enable_untracked_deallocations`], in which case `source_group_id` is
+    /// [`AllocationGroupId::UNTRACKED`].
+    ///
     /// All allocations/deallocations that occur within the call to `AllocationTracker::deallocated` are ignored, so
     /// implementors can allocate/deallocate without risk of reentrancy bugs. It does mean, however, that the
     /// allocations/deallocations that occur will be effectively lost, so implementors should ensure that the only data
@@ -104,7 +227,8 @@ pub trait AllocationTracker {
     /// As the allocator will customize the layout to include the group ID which owns an allocation, we provide two
     /// sizes: the object size and the wrapped size. The object size is the original layout of the allocation, and is
     /// valid against the given object address. The wrapped size is the true size of the underlying allocation that is
-    /// made, and represents the actual memory usage for the given allocation.
+    /// made, and represents the actual memory usage for the given allocation. It includes both the group ID and any
+    /// padding needed to keep the allocation aligned, so [`tracking_overhead`] gives the full cost of tracking it.
     fn deallocated(
         &self,
         addr: usize,
@@ -113,6 +237,368 @@ pub trait AllocationTracker {
         source_group_id: AllocationGroupId,
         current_group_id: AllocationGroupId,
     );
+
+    /// Tracks when a deallocation has occurred, along with the layout of the allocation.
+    ///
+    /// This is what the allocator calls for every deallocation, and the default implementation calls
+    /// [`deallocated`][Self::deallocated] with the size of `object_layout`. See
+    /// [`allocated_with_layout`][Self::allocated_with_layout] for when implementing this is useful.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::deallocated_with_layout` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    fn deallocated_with_layout(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        self.deallocated(
+            addr,
+            object_layout.size(),
+            wrapped_size,
+            source_group_id,
+            current_group_id,
+        );
+    }
+
+    /// Tracks when an allocation has occurred, along with the thread it occurred on.
+    ///
+    /// When the `thread-context` feature is enabled, this is called for every allocation, with the thread that made the
+    /// allocation, which is useful for figuring out which threads of a pool are allocating on behalf of an allocation
+    /// group. The default implementation calls [`allocated_with_layout`][Self::allocated_with_layout]. Looking up the
+    /// name of the thread, via [`Thread::name`][std::thread::Thread::name], does not allocate.
+    ///
+    /// The thread is captured once per thread, the first time it allocates. Allocations made while the thread is being
+    /// torn down, after its thread-local state has been destroyed, are passed directly to
+    /// [`allocated_with_layout`][Self::allocated_with_layout] instead.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::allocated_on_thread` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    #[cfg(feature = "thread-context")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "thread-context")))]
+    fn allocated_on_thread(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+        thread: &std::thread::Thread,
+    ) {
+        let _ = thread;
+        self.allocated_with_layout(addr, object_layout, wrapped_size, group_id);
+    }
+
+    /// Tracks when a deallocation has occurred, along with the thread it occurred on.
+    ///
+    /// This is the deallocation counterpart to [`allocated_on_thread`][Self::allocated_on_thread], and the default
+    /// implementation calls [`deallocated_with_layout`][Self::deallocated_with_layout].
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::deallocated_on_thread` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    #[cfg(feature = "thread-context")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "thread-context")))]
+    fn deallocated_on_thread(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        thread: &std::thread::Thread,
+    ) {
+        let _ = thread;
+        self.deallocated_with_layout(
+            addr,
+            object_layout,
+            wrapped_size,
+            source_group_id,
+            current_group_id,
+        );
+    }
+
+    /// Tracks when an allocation is deallocated in a different allocation group than the one that made it.
+    ///
+    /// This is called in addition to, not instead of, [`deallocated_with_layout`][Self::deallocated_with_layout] or
+    /// [`reallocated`][Self::reallocated], whenever `source_group_id` and `current_group_id` differ, which often points
+    /// to memory being handed off between subsystems. Trackers that are only interested in these deallocations can
+    /// implement this method rather than comparing group IDs on every deallocation. For reallocations, `object_size` is
+    /// the size of the original allocation. Deallocations of untracked allocations are never passed to this method.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::deallocated_cross_group` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    fn deallocated_cross_group(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        let _ = (addr, object_size, source_group_id, current_group_id);
+    }
+
+    /// Tracks when an allocation is deallocated, along with how long it lived.
+    ///
+    /// `age` is the number of allocations that were tracked, across all threads, between this allocation being made and
+    /// it being deallocated, which gives a rough measure of lifetime that doesn't depend on wall-clock time.
+    /// Reallocations tracked via [`reallocated`][Self::reallocated] keep the age of the original allocation. This is
+    /// called in addition to, not instead of, [`deallocated_with_layout`][Self::deallocated_with_layout], and
+    /// deallocations of untracked allocations are never passed to this method.
+    ///
+    /// The sequence number is stored in the trailer of every allocation, as a `u64`, which widens the trailer to 16
+    /// bytes, aligned to eight. This cancels out the savings of the `compact-header` feature, so the two shouldn't be
+    /// combined when overhead matters.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::deallocated_with_age` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    #[cfg(feature = "track-lifetime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "track-lifetime")))]
+    fn deallocated_with_age(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        age: u64,
+    ) {
+        let _ = (addr, object_size, source_group_id, age);
+    }
+
+    /// Tracks when an allocation has occurred, along with its event sequence number.
+    ///
+    /// When the `event-sequence` feature is enabled, every tracked allocation, deallocation, and reallocation takes a
+    /// sequence number from a single, process-wide counter, which gives a total order of events across all threads.
+    /// This makes it possible to log events per thread, and merge the logs afterwards by sorting on the sequence
+    /// number. Allocations take their sequence number after the memory has been handed out, and deallocations before
+    /// it's handed back, so when an address is freed and reused, the deallocation always comes first. Every
+    /// deallocation advances the counter, whether or not it's tracked, so sequence numbers have gaps.
+    ///
+    /// This is called in addition to, not instead of, [`allocated_with_layout`][Self::allocated_with_layout].
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::allocated_with_sequence` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    #[cfg(feature = "event-sequence")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "event-sequence")))]
+    fn allocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        let _ = (addr, object_size, group_id, sequence);
+    }
+
+    /// Tracks when a deallocation has occurred, along with its event sequence number.
+    ///
+    /// This is the deallocation counterpart to [`allocated_with_sequence`][Self::allocated_with_sequence], and is
+    /// called in addition to, not instead of, [`deallocated_with_layout`][Self::deallocated_with_layout].
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::deallocated_with_sequence` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    #[cfg(feature = "event-sequence")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "event-sequence")))]
+    fn deallocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        let _ = (
+            addr,
+            object_size,
+            source_group_id,
+            current_group_id,
+            sequence,
+        );
+    }
+
+    /// Tracks when an allocation has been reallocated, along with its event sequence number.
+    ///
+    /// This is the reallocation counterpart to [`allocated_with_sequence`][Self::allocated_with_sequence], and is
+    /// called in addition to, not instead of, [`reallocated`][Self::reallocated]. The reallocation takes its sequence
+    /// number once the wrapped allocator has returned, so if the block moved, an allocation on another thread that
+    /// reuses the original block may be ordered before it. The default implementation calls
+    /// [`deallocated_with_sequence`][Self::deallocated_with_sequence] for the original block, followed by
+    /// [`allocated_with_sequence`][Self::allocated_with_sequence] for the reallocated block, both with the same
+    /// sequence number.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::reallocated_with_sequence` are
+    /// ignored, so implementors can allocate/deallocate without risk of reentrancy bugs.
+    #[cfg(feature = "event-sequence")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "event-sequence")))]
+    #[allow(clippy::too_many_arguments)]
+    fn reallocated_with_sequence(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_size: usize,
+        new_object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        self.deallocated_with_sequence(
+            old_addr,
+            old_object_size,
+            source_group_id,
+            current_group_id,
+            sequence,
+        );
+        self.allocated_with_sequence(new_addr, new_object_size, current_group_id, sequence);
+    }
+
+    /// Tracks when an allocation has been reallocated.
+    ///
+    /// This is called when a tracked allocation is grown or shrunk, and the reallocated block is tracked as well,
+    /// whether the block was resized in place or moved to `new_addr`. `source_group_id` is the group ID that made the
+    /// original allocation, and `current_group_id` is the group ID active at the time of the reallocation, which the
+    /// reallocated block is attributed to from then on. If only one of the original block or the reallocated block is
+    /// tracked, such as when the new size falls outside of the tracked size range, then only
+    /// [`deallocated_with_layout`][Self::deallocated_with_layout] or
+    /// [`allocated_with_layout`][Self::allocated_with_layout] is called, respectively. The alignment of the allocation
+    /// never changes when reallocating, so the layout of the reallocated block is `old_object_layout` with a size of
+    /// `new_object_size`.
+    ///
+    /// The default implementation calls [`deallocated_with_layout`][Self::deallocated_with_layout] for the original
+    /// block, followed by [`allocated_with_layout`][Self::allocated_with_layout] for the reallocated block, so trackers
+    /// only need to implement this in order to account for reallocations precisely, such as tracking the growth of a
+    /// vector as a delta rather than as an entirely new allocation.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::reallocated` are ignored, so
+    /// implementors can allocate/deallocate without risk of reentrancy bugs.
+    #[allow(clippy::too_many_arguments)]
+    fn reallocated(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_layout: Layout,
+        new_object_size: usize,
+        old_wrapped_size: usize,
+        new_wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        // SAFETY: The allocator only ever calls this for a reallocation that succeeded, which means the new size was
+        // valid for the alignment of the original layout.
+        let new_object_layout = unsafe {
+            Layout::from_size_align_unchecked(new_object_size, old_object_layout.align())
+        };
+
+        report_deallocated(
+            self,
+            old_addr,
+            old_object_layout,
+            old_wrapped_size,
+            source_group_id,
+            current_group_id,
+        );
+        report_allocated(
+            self,
+            new_addr,
+            new_object_layout,
+            new_wrapped_size,
+            current_group_id,
+        );
+    }
+
+    /// Tracks when an allocation is about to be made.
+    ///
+    /// This is called before the wrapped allocator is asked for memory, with the layout of the object being allocated
+    /// and the allocation group that is making it, and so it sees every allocation attempt, including those that
+    /// ultimately fail. It can't change or fail the allocation in any way, which makes it a good fit for dry-run
+    /// accounting, such as simulating how much memory a workload would need under a different allocator. Allocations
+    /// that succeed and are tracked are then passed to [`allocated`][Self::allocated] as usual.
+    ///
+    /// Reallocations are reported too, with the layout that the object is being reallocated to. Only allocations within
+    /// the tracked size range are reported, but as sampling is only decided once an allocation has succeeded, every
+    /// allocation is reported here regardless of the sampling rate.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::allocating` are ignored.
+    fn allocating(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        let _ = (object_layout, group_id);
+    }
+
+    /// Tracks when an allocation has failed.
+    ///
+    /// This is called when the wrapped allocator runs out of memory, right before the allocation error handler is
+    /// called, which by default aborts the process. `object_layout` is the layout of the object that could not be
+    /// allocated, and `group_id` is the allocation group that was making it, which makes this the place to log the
+    /// size and allocation group at fault when diagnosing out-of-memory conditions.
+    ///
+    /// This is strictly best-effort: memory has already run out, so implementations must not allocate, and should
+    /// instead write out what they need directly, such as to stderr via a stack buffer, or to a preallocated buffer. It
+    /// is only called for failures of the wrapped allocator, not for allocations that are failed because their layout
+    /// cannot be tracked, or by [`should_fail_allocation`][Self::should_fail_allocation] when fault injection is
+    /// enabled.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::allocation_failed` are ignored.
+    fn allocation_failed(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        let _ = (object_layout, group_id);
+    }
+
+    /// Decides whether an allocation should fail.
+    ///
+    /// This is called before every allocation is made, and if `true` is returned, the allocation is failed as if the
+    /// wrapped allocator had run out of memory, without calling the wrapped allocator at all. This makes it possible to
+    /// deterministically exercise the out-of-memory handling of code under test, such as the error paths of
+    /// `Vec::try_reserve`.
+    ///
+    /// This is strictly a testing tool, and should never be used in production. Allocations that are failed this way
+    /// are not passed to [`allocated`][Self::allocated]. Bear in mind that most of the standard library aborts the
+    /// process when an allocation fails, so failures should be targeted narrowly, such as by allocation group and size.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::should_fail_allocation` are
+    /// ignored, and never failed.
+    #[cfg(feature = "fault-injection")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fault-injection")))]
+    fn should_fail_allocation(&self, object_size: usize, group_id: AllocationGroupId) -> bool {
+        let _ = (object_size, group_id);
+        false
+    }
+
+    /// Tracks when an allocation group has been registered.
+    ///
+    /// This is called once for every allocation group registered via [`AllocationGroupToken::register`], regardless of
+    /// whether any allocations are ever made while it is active, which allows trackers to know about every allocation
+    /// group up front, such as to render groups that haven't allocated anything yet. Allocation groups registered
+    /// before the global tracker was installed, or while tracking was disabled, are not reported.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::group_registered` are ignored.
+    fn group_registered(&self, group_id: AllocationGroupId) {
+        let _ = group_id;
+    }
+
+    /// Called once when the tracker is installed as the global tracker.
+    ///
+    /// This is called by [`AllocationRegistry::set_global_tracker`] and [`AllocationRegistry::set_scoped_tracker`]
+    /// before the tracker is installed, so it always returns before any other method of the tracker is called,
+    /// including the first call to [`allocated`][Self::allocated]. By then, the global allocator is active, which makes
+    /// this a safe place for one-time setup that needs to allocate, like building any internal maps, rather than doing
+    /// it at static initialization time or lazily on the allocation path. If another tracker is installed concurrently,
+    /// causing `set_global_tracker` to fail, the tracker is never used after this.
+    ///
+    /// All allocations/deallocations that occur within the call to `AllocationTracker::on_install` are ignored.
+    fn on_install(&self) {}
+
+    /// Tracks when a thread exits while an allocation group is still active on it.
+    ///
+    /// Every allocation group that is entered on a thread should be exited on that same thread, but a guard that is
+    /// leaked, such as via [`std::mem::forget`], or an allocation group entered over FFI without a matching exit, will
+    /// leave the allocation group active until the thread exits. This is harmless for the allocator itself, but almost
+    /// always points to a bug in how allocation groups are being managed. `group_id` is the allocation group that was
+    /// active when the thread exited.
+    ///
+    /// This is called while the thread-local state of the exiting thread is being torn down, so any allocations or
+    /// deallocations that occur within the call are never tracked. Depending on the platform, thread-local state may
+    /// not be torn down for the main thread, in which case this is never called for it.
+    #[cfg(feature = "thread-exit-check")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "thread-exit-check")))]
+    fn unbalanced_thread_exit(&self, group_id: AllocationGroupId) {
+        let _ = group_id;
+    }
 }
 
 struct Tracker {
@@ -133,30 +619,225 @@ impl Tracker {
     fn allocated(
         &self,
         addr: usize,
-        object_size: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        observer::with_observer(|| {
+            report_allocated(&*self.tracker, addr, object_layout, wrapped_size, group_id);
+        });
+    }
+
+    /// Tracks when a zeroed allocation has occurred.
+    fn allocated_zeroed(
+        &self,
+        addr: usize,
+        object_layout: Layout,
         wrapped_size: usize,
         group_id: AllocationGroupId,
     ) {
-        self.tracker
-            .allocated(addr, object_size, wrapped_size, group_id);
+        observer::with_observer(|| {
+            self.tracker
+                .allocated_zeroed(addr, object_layout, wrapped_size, group_id);
+        });
     }
 
     /// Tracks when a deallocation has occurred.
     fn deallocated(
         &self,
         addr: usize,
-        object_size: usize,
+        object_layout: Layout,
         wrapped_size: usize,
         source_group_id: AllocationGroupId,
         current_group_id: AllocationGroupId,
     ) {
-        self.tracker.deallocated(
-            addr,
-            object_size,
-            wrapped_size,
-            source_group_id,
-            current_group_id,
-        );
+        observer::with_observer(|| {
+            let cross_group = (source_group_id != current_group_id
+                && source_group_id != AllocationGroupId::UNTRACKED)
+                .then_some((source_group_id, current_group_id));
+
+            report_deallocated(
+                &*self.tracker,
+                addr,
+                object_layout,
+                wrapped_size,
+                source_group_id,
+                current_group_id,
+            );
+
+            if let Some((source_group_id, current_group_id)) = cross_group {
+                self.tracker.deallocated_cross_group(
+                    addr,
+                    object_layout.size(),
+                    source_group_id,
+                    current_group_id,
+                );
+            }
+        });
+    }
+
+    /// Tracks when an allocation is deallocated, along with how long it lived.
+    #[cfg(feature = "track-lifetime")]
+    fn deallocated_with_age(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        age: u64,
+    ) {
+        observer::with_observer(|| {
+            self.tracker
+                .deallocated_with_age(addr, object_size, source_group_id, age);
+        });
+    }
+
+    /// Tracks when an allocation has occurred, along with its event sequence number.
+    #[cfg(feature = "event-sequence")]
+    fn allocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        observer::with_observer(|| {
+            self.tracker
+                .allocated_with_sequence(addr, object_size, group_id, sequence);
+        });
+    }
+
+    /// Tracks when a deallocation has occurred, along with its event sequence number.
+    #[cfg(feature = "event-sequence")]
+    fn deallocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        observer::with_observer(|| {
+            self.tracker.deallocated_with_sequence(
+                addr,
+                object_size,
+                source_group_id,
+                current_group_id,
+                sequence,
+            );
+        });
+    }
+
+    /// Tracks when an allocation has been reallocated, along with its event sequence number.
+    #[cfg(feature = "event-sequence")]
+    #[allow(clippy::too_many_arguments)]
+    fn reallocated_with_sequence(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_size: usize,
+        new_object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        observer::with_observer(|| {
+            self.tracker.reallocated_with_sequence(
+                old_addr,
+                new_addr,
+                old_object_size,
+                new_object_size,
+                source_group_id,
+                current_group_id,
+                sequence,
+            );
+        });
+    }
+
+    /// Tracks when an allocation has been reallocated.
+    #[allow(clippy::too_many_arguments)]
+    fn reallocated(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_layout: Layout,
+        new_object_size: usize,
+        old_wrapped_size: usize,
+        new_wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        observer::with_observer(|| {
+            let cross_group = (source_group_id != current_group_id)
+                .then_some((source_group_id, current_group_id));
+
+            self.tracker.reallocated(
+                old_addr,
+                new_addr,
+                old_object_layout,
+                new_object_size,
+                old_wrapped_size,
+                new_wrapped_size,
+                source_group_id,
+                current_group_id,
+            );
+
+            if let Some((source_group_id, current_group_id)) = cross_group {
+                self.tracker.deallocated_cross_group(
+                    old_addr,
+                    old_object_layout.size(),
+                    source_group_id,
+                    current_group_id,
+                );
+            }
+        });
+    }
+}
+
+impl Tracker {
+    /// Tracks when an allocation is about to be made.
+    fn allocating(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        observer::with_observer(|| {
+            self.tracker.allocating(object_layout, group_id);
+        });
+    }
+
+    /// Tracks when an allocation has failed.
+    fn allocation_failed(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        observer::with_observer(|| {
+            self.tracker.allocation_failed(object_layout, group_id);
+        });
+    }
+
+    /// Tracks when an allocation group has been registered.
+    fn group_registered(&self, group_id: AllocationGroupId) {
+        observer::with_observer(|| {
+            self.tracker.group_registered(group_id);
+        });
+    }
+
+    /// Lets the tracker set itself up before it's installed.
+    fn on_install(&self) {
+        with_suspended_allocation_group(|| {
+            observer::with_observer(|| self.tracker.on_install());
+        });
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+impl Tracker {
+    /// Decides whether an allocation should fail.
+    fn should_fail_allocation(&self, object_size: usize, group_id: AllocationGroupId) -> bool {
+        self.tracker.should_fail_allocation(object_size, group_id)
+    }
+}
+
+#[cfg(feature = "thread-exit-check")]
+impl Tracker {
+    /// Tracks when a thread exits while an allocation group is still active on it.
+    fn unbalanced_thread_exit(&self, group_id: AllocationGroupId) {
+        observer::with_observer(|| {
+            self.tracker.unbalanced_thread_exit(group_id);
+        });
     }
 }
 
@@ -174,6 +855,21 @@ impl fmt::Display for SetTrackerError {
 
 impl error::Error for SetTrackerError {}
 
+/// Guard that restores the previous global tracker when dropped.
+///
+/// Created by [`AllocationRegistry::set_scoped_tracker`]. If no global tracker was set when the guard was created, then
+/// the global tracker is cleared when the guard is dropped.
+#[must_use = "the scoped tracker is replaced as soon as the guard is dropped"]
+pub struct ScopedTrackerGuard {
+    previous: *mut Tracker,
+}
+
+impl Drop for ScopedTrackerGuard {
+    fn drop(&mut self) {
+        GLOBAL_TRACKER.store(self.previous, Ordering::Release);
+    }
+}
+
 /// Handles registering tokens for tracking different allocation groups.
 pub struct AllocationRegistry;
 
@@ -184,40 +880,243 @@ impl AllocationRegistry {
     }
 
     /// Disables the tracking of allocations.
+    ///
+    /// Allocations made while tracking is disabled are never attributed to an allocation group, so their deallocations
+    /// are skipped even if tracking has been enabled again by the time they happen.
     pub fn disable_tracking() {
         TRACKING_ENABLED.store(false, Ordering::SeqCst);
     }
 
+    /// Whether or not the tracking of allocations is enabled.
+    #[must_use]
+    pub fn is_tracking_enabled() -> bool {
+        tracking_enabled()
+    }
+
+    /// Enables passing deallocations of untracked allocations to the tracker.
+    ///
+    /// By default, deallocations are only passed to the tracker if the allocation itself was tracked, which keeps the
+    /// deallocation path for untracked allocations as cheap as possible. Some trackers, however, want to reconcile
+    /// their totals against everything that was freed, such as allocations that were made before tracking was enabled.
+    ///
+    /// When enabled, [`AllocationTracker::deallocated`] is also called for untracked allocations, with a source group
+    /// ID of [`AllocationGroupId::UNTRACKED`].
+    pub fn enable_untracked_deallocations() {
+        UNTRACKED_DEALLOCATIONS_ENABLED.store(true, Ordering::SeqCst);
+    }
+
+    /// Disables passing deallocations of untracked allocations to the tracker.
+    pub fn disable_untracked_deallocations() {
+        UNTRACKED_DEALLOCATIONS_ENABLED.store(false, Ordering::SeqCst);
+    }
+
+    /// Enables suspending tracking while the current thread is panicking.
+    ///
+    /// By default, allocations made while a thread is unwinding from a panic, such as when formatting the panic message
+    /// or by destructors that allocate, are attributed to whichever allocation group is active at the time, exactly as
+    /// any other allocation would be. This can be surprising when looking at per-group statistics, as the allocations
+    /// have little to do with the work the allocation group represents.
+    ///
+    /// When enabled, allocations made while [`std::thread::panicking`] returns `true` are left untracked, so they are
+    /// never passed to the tracker. Deallocations are unaffected: deallocating a tracked allocation while panicking is
+    /// still passed to the tracker, attributed to the allocation group that made the allocation, which keeps per-group
+    /// totals balanced.
+    pub fn enable_panic_suspension() {
+        PANIC_SUSPENSION_ENABLED.store(true, Ordering::SeqCst);
+    }
+
+    /// Disables suspending tracking while the current thread is panicking.
+    pub fn disable_panic_suspension() {
+        PANIC_SUSPENSION_ENABLED.store(false, Ordering::SeqCst);
+    }
+
+    /// Enables accounting for allocations made by the tracker itself.
+    ///
+    /// Allocations made while the tracker is handling an allocation or deallocation event are normally untracked, which
+    /// keeps the tracker from reentrantly tracking its own allocations, but also hides the memory the tracker itself
+    /// consumes. When observer accounting is enabled, those allocations are instead attributed to
+    /// [`AllocationGroupId::OBSERVER`], and counted in [`observer_usage`][Self::observer_usage], so that the overhead
+    /// of tracking can be reported honestly.
+    ///
+    /// Unlike regular allocation groups, allocations attributed to the observer group are never passed to the tracker,
+    /// as doing so would reentrantly call the tracker from within itself. Allocations made within
+    /// [`untracked`][Self::untracked], outside of the tracker, are not affected, and stay fully untracked.
+    pub fn enable_observer_accounting() {
+        observer::set_observer_accounting(true);
+    }
+
+    /// Disables accounting for allocations made by the tracker itself.
+    ///
+    /// Allocations that were attributed to the observer group while observer accounting was enabled are still counted
+    /// when they are deallocated.
+    pub fn disable_observer_accounting() {
+        observer::set_observer_accounting(false);
+    }
+
+    /// Gets the memory used by the tracker itself, as seen by observer accounting.
+    #[must_use]
+    pub fn observer_usage() -> ObserverUsage {
+        observer::observer_usage()
+    }
+
+    /// Sets a hook that is called whenever the active allocation group changes on a thread.
+    ///
+    /// The hook is called, on the thread where the change happened, every time an allocation group is entered or
+    /// exited, with the allocation group that is active after the change. When no allocation group is active, the hook
+    /// is called with [`AllocationGroupId::ROOT`]. This makes it possible to mirror the active allocation group into
+    /// another system, such as the context of a profiler, without having to instrument every place where allocation
+    /// groups are entered and exited.
+    ///
+    /// The hook is called on every enter and exit, so it should be cheap. Allocations made within the hook are not
+    /// tracked, and the hook must not itself enter or exit allocation groups, as doing so panics. Setting a hook
+    /// replaces any hook that was previously set.
+    pub fn set_context_change_hook(hook: fn(AllocationGroupId)) {
+        token::set_context_change_hook(Some(hook));
+    }
+
+    /// Clears the hook that is called whenever the active allocation group changes on a thread.
+    pub fn clear_context_change_hook() {
+        token::set_context_change_hook(None);
+    }
+
+    /// Sets a human-readable name for the given allocation group.
+    ///
+    /// Names are purely informational, and are intended to provide concise labels for allocation groups when reporting
+    /// on them, such as in dashboards. Setting a name for an allocation group replaces any name that was previously
+    /// set.
+    pub fn set_group_name(id: &AllocationGroupId, name: &'static str) {
+        metadata::update_group_metadata(*id, |metadata| metadata.name = Some(name));
+    }
+
+    /// Gets the human-readable name of the given allocation group, if one has been set.
+    #[must_use]
+    pub fn group_name(id: &AllocationGroupId) -> Option<&'static str> {
+        metadata::with_group_metadata(*id, |metadata| metadata.and_then(|metadata| metadata.name))
+    }
+
+    /// Gets the parent of the given allocation group, if it was registered with one.
+    ///
+    /// Allocation groups registered via [`AllocationGroupToken::register_with_parent`] form a tree, which trackers can
+    /// walk by calling this repeatedly in order to aggregate the allocations of each allocation group into its
+    /// ancestors.
+    #[must_use]
+    pub fn group_parent(id: &AllocationGroupId) -> Option<AllocationGroupId> {
+        metadata::with_group_metadata(*id, |metadata| {
+            metadata.and_then(|metadata| metadata.parent)
+        })
+    }
+
+    /// Gets a label for the given allocation group, suitable for reporting.
+    ///
+    /// If a name has been set for the allocation group, the name is returned, otherwise the label is derived from the
+    /// group ID itself, in the form of `group#<id>`.
+    #[must_use]
+    pub fn group_label(id: &AllocationGroupId) -> Cow<'static, str> {
+        match Self::group_name(id) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(format!("group#{}", id.as_usize())),
+        }
+    }
+
+    /// Gets the number of allocation groups that have been registered so far.
+    ///
+    /// This counts every allocation group registered via [`AllocationGroupToken`], including those registered by name,
+    /// with a parent, or in bulk, but not the root allocation group. Group IDs are never reused, so this only ever
+    /// increases, and gives an idea of how close an application is to exhausting the group ID space. It's safe to call
+    /// concurrently with registration, in which case the count may not yet include allocation groups that are still
+    /// being registered.
+    #[must_use]
+    pub fn registered_group_count() -> usize {
+        token::registered_group_count()
+    }
+
+    /// Calls `f` with every registered allocation group, along with its name, if one has been set.
+    ///
+    /// Allocation groups are visited in ascending order of their IDs, and the root allocation group is not included.
+    /// The set of allocation groups to visit is fixed before the first call to `f`, so each allocation group is visited
+    /// exactly once, even if others are being registered concurrently, in which case those may not be visited. Names
+    /// are looked up at that same point, so `f` is free to set names, or register allocation groups, itself.
+    pub fn for_each_group<F>(mut f: F)
+    where
+        F: FnMut(AllocationGroupId, Option<&'static str>),
+    {
+        let mut names = metadata::group_names().into_iter().peekable();
+        for id in AllocationGroupId::registered() {
+            // Both are ordered by group ID, so we can match names up to groups as we go.
+            while names.next_if(|(named_id, _)| *named_id < id).is_some() {}
+            let name = names
+                .next_if(|(named_id, _)| *named_id == id)
+                .map(|(_, name)| name);
+            f(id, name);
+        }
+    }
+
     /// Sets the global tracker.
     ///
     /// Setting a global tracker does not enable or disable the tracking of allocations, so callers
     /// still need to call `enable_tracking` after this in order to fully enable tracking.
     ///
+    /// Allocations made before a tracker is set are never attributed to an allocation group, even if one was active
+    /// and tracking was enabled, so the tracker is never told about their deallocation either, unless untracked
+    /// deallocations have been enabled via [`enable_untracked_deallocations`][Self::enable_untracked_deallocations].
+    /// This means that a tracker can be set at any point without seeing deallocations that it never saw allocated.
+    ///
     /// # Errors
     /// `Err(SetTrackerError)` is returned if a global tracker has already been set, otherwise `Ok(())`.
     pub fn set_global_tracker<T>(tracker: T) -> Result<(), SetTrackerError>
     where
         T: AllocationTracker + Send + Sync + 'static,
     {
-        if GLOBAL_INIT
+        if global_tracker_installed() {
+            return Err(SetTrackerError { _sealed: () });
+        }
+
+        // Just like with `set_scoped_tracker`, the allocations made to hold the tracker aren't attributed to whichever
+        // allocation group happens to be active, as they live for the rest of the process.
+        let tracker = Self::untracked(|| Tracker::from_allocation_tracker(tracker));
+        tracker.on_install();
+
+        let tracker = Self::untracked(|| Box::into_raw(Box::new(tracker)));
+        if GLOBAL_TRACKER
             .compare_exchange(
-                UNINITIALIZED,
-                INITIALIZING,
+                ptr::null_mut(),
+                tracker,
                 Ordering::AcqRel,
-                Ordering::Relaxed,
+                Ordering::Acquire,
             )
             .is_ok()
         {
-            unsafe {
-                GLOBAL_TRACKER = Some(Tracker::from_allocation_tracker(tracker));
-            }
-            GLOBAL_INIT.store(INITIALIZED, Ordering::Release);
             Ok(())
         } else {
+            // SAFETY: The tracker was never installed, so nothing else can have a reference to it.
+            Self::untracked(|| drop(unsafe { Box::from_raw(tracker) }));
             Err(SetTrackerError { _sealed: () })
         }
     }
 
+    /// Sets the global tracker until the returned guard is dropped.
+    ///
+    /// Unlike [`set_global_tracker`][Self::set_global_tracker], this replaces any global tracker that is already set,
+    /// and restores it when the returned guard is dropped. This is primarily useful in tests, where each test may want
+    /// to install its own tracker: applications should generally set the global tracker once, at startup.
+    ///
+    /// Replacing the global tracker is safe even while other threads are allocating, as trackers are never dropped once
+    /// they have been installed, even after being replaced. As such, every call to this method leaks the given tracker.
+    /// If guards are nested, they should be dropped in the reverse order they were created in, as each guard restores
+    /// whichever tracker was set when it was created.
+    pub fn set_scoped_tracker<T>(tracker: T) -> ScopedTrackerGuard
+    where
+        T: AllocationTracker + Send + Sync + 'static,
+    {
+        let tracker = Self::untracked(|| Tracker::from_allocation_tracker(tracker));
+        tracker.on_install();
+
+        let tracker = Self::untracked(|| Box::into_raw(Box::new(tracker)));
+        let previous = GLOBAL_TRACKER.swap(tracker, Ordering::AcqRel);
+
+        ScopedTrackerGuard { previous }
+    }
+
     /// Runs the given closure without tracking allocations or deallocations.
     ///
     /// Inevitably, users of this crate will need to allocate storage for the actual data being tracked. While
@@ -232,25 +1131,124 @@ impl AllocationRegistry {
 
     /// Clears the global tracker.
     ///
-    /// # Safety
-    ///
-    /// Well, there is none.  It's not safe.  This method clears the static reference to the
-    /// tracker, which means we're violating the central assumption that a reference with a
-    /// `'static` lifetime is valid for the lifetime of the process.
-    ///
-    /// All of this said, you're looking at the code comments for a function that is intended to be
-    /// hidden from the docs, so here's where this function may be useful: in tests.
-    ///
-    /// If you can ensure that only one thread is running, thus ensuring there will be no competing
-    /// concurrent accesses, then this is safe.  Also, of course, this leaks whatever allocation
-    /// tracker was set before. Likely not a problem in tests, but for posterity's sake..
+    /// As installed trackers are never dropped, this is safe to call even while other threads are allocating.
+    /// [`set_scoped_tracker`][Self::set_scoped_tracker] should be preferred in tests.
     ///
-    /// YOU'VE BEEN WARNED. :)
+    /// Of course, this leaks whatever allocation tracker was set before. Likely not a problem in tests, but for
+    /// posterity's sake..
     #[doc(hidden)]
-    pub unsafe fn clear_global_tracker() {
-        GLOBAL_INIT.store(INITIALIZING, Ordering::Release);
-        GLOBAL_TRACKER = None;
-        GLOBAL_INIT.store(UNINITIALIZED, Ordering::Release);
+    pub fn clear_global_tracker() {
+        GLOBAL_TRACKER.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+fn tracking_enabled() -> bool {
+    TRACKING_ENABLED.load(Ordering::Relaxed)
+}
+
+fn global_tracker_installed() -> bool {
+    !GLOBAL_TRACKER.load(Ordering::Acquire).is_null()
+}
+
+#[inline(always)]
+fn untracked_deallocations_enabled() -> bool {
+    UNTRACKED_DEALLOCATIONS_ENABLED.load(Ordering::Relaxed)
+}
+
+#[inline(always)]
+fn panic_suspension_enabled() -> bool {
+    PANIC_SUSPENSION_ENABLED.load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "thread-context")]
+thread_local! {
+    /// The current thread, captured the first time it's needed, so that it can still be handed out for as long as the
+    /// thread-local state of the thread is available.
+    static CURRENT_THREAD: std::cell::OnceCell<std::thread::Thread> = const { std::cell::OnceCell::new() };
+}
+
+/// Reports an allocation to the given tracker, along with the current thread if the `thread-context` feature is
+/// enabled.
+#[inline(always)]
+fn report_allocated<T: AllocationTracker + ?Sized>(
+    tracker: &T,
+    addr: usize,
+    object_layout: Layout,
+    wrapped_size: usize,
+    group_id: AllocationGroupId,
+) {
+    #[cfg(feature = "thread-context")]
+    let group_id = {
+        let mut group_id = Some(group_id);
+        let _ = CURRENT_THREAD.try_with(|thread| {
+            if let Some(group_id) = group_id.take() {
+                let thread = thread.get_or_init(std::thread::current);
+                tracker.allocated_on_thread(addr, object_layout, wrapped_size, group_id, thread);
+            }
+        });
+
+        match group_id {
+            Some(group_id) => group_id,
+            None => return,
+        }
+    };
+
+    tracker.allocated_with_layout(addr, object_layout, wrapped_size, group_id);
+}
+
+/// Reports a deallocation to the given tracker, along with the current thread if the `thread-context` feature is
+/// enabled.
+#[inline(always)]
+fn report_deallocated<T: AllocationTracker + ?Sized>(
+    tracker: &T,
+    addr: usize,
+    object_layout: Layout,
+    wrapped_size: usize,
+    source_group_id: AllocationGroupId,
+    current_group_id: AllocationGroupId,
+) {
+    #[cfg(feature = "thread-context")]
+    let (source_group_id, current_group_id) = {
+        let mut group_ids = Some((source_group_id, current_group_id));
+        let _ = CURRENT_THREAD.try_with(|thread| {
+            if let Some((source_group_id, current_group_id)) = group_ids.take() {
+                let thread = thread.get_or_init(std::thread::current);
+                tracker.deallocated_on_thread(
+                    addr,
+                    object_layout,
+                    wrapped_size,
+                    source_group_id,
+                    current_group_id,
+                    thread,
+                );
+            }
+        });
+
+        match group_ids {
+            Some(group_ids) => group_ids,
+            None => return,
+        }
+    };
+
+    tracker.deallocated_with_layout(
+        addr,
+        object_layout,
+        wrapped_size,
+        source_group_id,
+        current_group_id,
+    );
+}
+
+fn report_group_registered(group_id: AllocationGroupId) {
+    if let Some(tracker) = get_global_tracker() {
+        with_suspended_allocation_group(|| tracker.group_registered(group_id));
+    }
+}
+
+#[cfg(feature = "thread-exit-check")]
+fn report_unbalanced_thread_exit(group_id: AllocationGroupId) {
+    if let Some(tracker) = get_global_tracker() {
+        tracker.unbalanced_thread_exit(group_id);
     }
 }
 
@@ -262,14 +1260,7 @@ fn get_global_tracker() -> Option<&'static Tracker> {
     }
 
     // Tracker has to actually be installed.
-    if GLOBAL_INIT.load(Ordering::Acquire) != INITIALIZED {
-        return None;
-    }
-
-    unsafe {
-        let tracker = GLOBAL_TRACKER
-            .as_ref()
-            .expect("global tracked marked as initialized, but failed to unwrap");
-        Some(tracker)
-    }
+    //
+    // SAFETY: Installed trackers are leaked, and never freed, so any non-null pointer is valid for `'static`.
+    unsafe { GLOBAL_TRACKER.load(Ordering::Acquire).as_ref() }
 }
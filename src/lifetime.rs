@@ -0,0 +1,48 @@
+#[cfg(feature = "track-lifetime")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of allocations that have been stamped with a sequence number so far.
+#[cfg(feature = "track-lifetime")]
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// The point in the sequence of tracked allocations at which an allocation was made.
+///
+/// With the `track-lifetime` feature, every tracked allocation is stamped with the number of allocations that were
+/// tracked before it, so that the age of the allocation, in terms of the number of allocations tracked since, can be
+/// given to the tracker when it's deallocated. Without it, this is zero-sized, and does nothing.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Sequence {
+    #[cfg(feature = "track-lifetime")]
+    value: u64,
+}
+
+impl Sequence {
+    /// Stamps a newly tracked allocation.
+    #[inline(always)]
+    pub fn next() -> Self {
+        Self {
+            #[cfg(feature = "track-lifetime")]
+            value: NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Creates a `Sequence` from its raw value.
+    #[cfg(feature = "track-lifetime")]
+    pub fn from_raw(value: u64) -> Self {
+        Self { value }
+    }
+
+    /// Gets the raw value of this `Sequence`.
+    #[cfg(feature = "track-lifetime")]
+    pub fn as_raw(self) -> u64 {
+        self.value
+    }
+
+    /// Gets the number of allocations that have been tracked since the allocation stamped with this `Sequence`.
+    #[cfg(feature = "track-lifetime")]
+    pub fn age(self) -> u64 {
+        NEXT_SEQUENCE
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.value + 1)
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{token::with_suspended_allocation_group_if_available, AllocationGroupId};
+
+/// Metadata associated with an allocation group.
+#[derive(Default)]
+pub(crate) struct GroupMetadata {
+    pub name: Option<&'static str>,
+    pub parent: Option<AllocationGroupId>,
+}
+
+/// Metadata for all allocation groups that have any, keyed by group ID.
+///
+/// Metadata is looked up far more often than it's updated, such as every time a group ID is formatted from within a
+/// tracker, so this is behind a read-write lock: lookups from any number of threads only ever wait on an update that's
+/// in progress, rather than on each other.
+static GROUP_METADATA: RwLock<BTreeMap<AllocationGroupId, GroupMetadata>> =
+    RwLock::new(BTreeMap::new());
+
+fn read_metadata() -> RwLockReadGuard<'static, BTreeMap<AllocationGroupId, GroupMetadata>> {
+    GROUP_METADATA
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_metadata() -> RwLockWriteGuard<'static, BTreeMap<AllocationGroupId, GroupMetadata>> {
+    GROUP_METADATA
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Calls `f` with the metadata for the given allocation group, if the group has any.
+///
+/// Any allocations made while looking up the metadata, or within `f` itself, are not tracked. This is safe to call from
+/// anywhere, including while the current thread is exiting, as group IDs are formatted with their names from within
+/// tracker callbacks such as `unbalanced_thread_exit`.
+pub(crate) fn with_group_metadata<F, R>(id: AllocationGroupId, f: F) -> R
+where
+    F: FnOnce(Option<&GroupMetadata>) -> R,
+{
+    with_suspended_allocation_group_if_available(|| f(read_metadata().get(&id)))
+}
+
+/// Calls `f` with the metadata for the given allocation group, creating it if the group doesn't yet have any.
+///
+/// Any allocations made while updating the metadata, or within `f` itself, are not tracked.
+pub(crate) fn update_group_metadata<F, R>(id: AllocationGroupId, f: F) -> R
+where
+    F: FnOnce(&mut GroupMetadata) -> R,
+{
+    with_suspended_allocation_group_if_available(|| f(write_metadata().entry(id).or_default()))
+}
+
+/// Gets the names of every allocation group that has one, ordered by group ID.
+///
+/// Any allocations made while collecting the names are not tracked.
+pub(crate) fn group_names() -> Vec<(AllocationGroupId, &'static str)> {
+    with_suspended_allocation_group_if_available(|| {
+        read_metadata()
+            .iter()
+            .filter_map(|(id, metadata)| metadata.name.map(|name| (*id, name)))
+            .collect()
+    })
+}
+
+/// Allocation groups registered by name, keyed by name.
+static NAMED_GROUPS: Mutex<BTreeMap<&'static str, AllocationGroupId>> = Mutex::new(BTreeMap::new());
+
+/// Gets the allocation group registered under the given name, calling `register` to register it if there isn't one yet.
+///
+/// Along with the group ID, this returns whether the allocation group was newly registered by this call. The lock is
+/// held while registering, so concurrent callers with the same name always end up with the same allocation group, which
+/// means that `register` must not report the new allocation group to the tracker itself: a tracker that registers named
+/// allocation groups of its own when told about a new allocation group would deadlock. Instead, callers report newly
+/// registered allocation groups once this returns, and the lock has been released. Any allocations made while looking
+/// up or registering the allocation group are not tracked.
+pub(crate) fn get_or_register_named_group<F>(
+    name: &'static str,
+    register: F,
+) -> Option<(AllocationGroupId, bool)>
+where
+    F: FnOnce() -> Option<AllocationGroupId>,
+{
+    with_suspended_allocation_group_if_available(|| {
+        let mut named_groups = NAMED_GROUPS.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(id) = named_groups.get(name) {
+            return Some((*id, false));
+        }
+
+        let id = register()?;
+        named_groups.insert(name, id);
+        Some((id, true))
+    })
+}
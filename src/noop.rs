@@ -0,0 +1,30 @@
+use crate::{AllocationGroupId, AllocationTracker};
+
+/// A tracker that does nothing.
+///
+/// Installing this tracker still exercises the full tracking path, including attributing allocations to allocation
+/// groups, but ignores every allocation event. This is useful for builds where tracking is configured conditionally,
+/// or for measuring the overhead of tracking itself, separately from the overhead of any particular tracker.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopTracker;
+
+impl AllocationTracker for NoopTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
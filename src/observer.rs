@@ -0,0 +1,102 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// Whether or not allocations made by the tracker itself should be accounted for.
+static OBSERVER_ACCOUNTING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static OBSERVER_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static OBSERVER_DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static OBSERVER_ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static OBSERVER_DEALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Whether or not the tracker is currently being called on this thread.
+    static IN_TRACKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Memory used by the tracker itself, as seen by observer accounting.
+///
+/// Returned by [`AllocationRegistry::observer_usage`][crate::AllocationRegistry::observer_usage].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObserverUsage {
+    /// Number of allocations made by the tracker.
+    pub allocations: usize,
+
+    /// Number of deallocations of allocations that were made by the tracker.
+    pub deallocations: usize,
+
+    /// Total bytes allocated by the tracker.
+    pub allocated_bytes: usize,
+
+    /// Total bytes deallocated from allocations that were made by the tracker.
+    pub deallocated_bytes: usize,
+}
+
+impl ObserverUsage {
+    /// Gets the number of bytes allocated by the tracker that are still live.
+    #[must_use]
+    pub fn live_bytes(&self) -> usize {
+        self.allocated_bytes.saturating_sub(self.deallocated_bytes)
+    }
+}
+
+pub(crate) fn set_observer_accounting(enabled: bool) {
+    OBSERVER_ACCOUNTING_ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub(crate) fn observer_usage() -> ObserverUsage {
+    ObserverUsage {
+        allocations: OBSERVER_ALLOCATIONS.load(Ordering::Relaxed),
+        deallocations: OBSERVER_DEALLOCATIONS.load(Ordering::Relaxed),
+        allocated_bytes: OBSERVER_ALLOCATED_BYTES.load(Ordering::Relaxed),
+        deallocated_bytes: OBSERVER_DEALLOCATED_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Calls `f`, marking the current thread as being within the tracker for the duration of the call.
+///
+/// If observer accounting is disabled, `f` is called directly.
+#[inline(always)]
+pub(crate) fn with_observer<F>(f: F)
+where
+    F: FnOnce(),
+{
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            let _ = IN_TRACKER.try_with(|in_tracker| in_tracker.set(false));
+        }
+    }
+
+    if OBSERVER_ACCOUNTING_ENABLED.load(Ordering::Relaxed)
+        && IN_TRACKER
+            .try_with(|in_tracker| in_tracker.set(true))
+            .is_ok()
+    {
+        let _reset = Reset;
+        f();
+    } else {
+        f();
+    }
+}
+
+/// Whether or not an allocation happening right now, on this thread, was made by the tracker.
+#[inline(always)]
+pub(crate) fn is_observer_allocation() -> bool {
+    OBSERVER_ACCOUNTING_ENABLED.load(Ordering::Relaxed)
+        && IN_TRACKER.try_with(Cell::get).unwrap_or(false)
+}
+
+pub(crate) fn record_allocation(object_size: usize) {
+    OBSERVER_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    OBSERVER_ALLOCATED_BYTES.fetch_add(object_size, Ordering::Relaxed);
+}
+
+pub(crate) fn record_deallocation(object_size: usize) {
+    OBSERVER_DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    OBSERVER_DEALLOCATED_BYTES.fetch_add(object_size, Ordering::Relaxed);
+}
@@ -0,0 +1,115 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::{token::with_suspended_allocation_group_if_available, AllocationGroupToken};
+
+/// A fixed-size pool of reusable allocation group tokens.
+///
+/// Registering an allocation group uses up a group ID for good, so applications that would otherwise register a fresh
+/// allocation group for every unit of work, such as each request handled by a server, eventually run out of them. When
+/// the number of concurrent units of work is bounded, a pool can instead register that many allocation groups up front,
+/// and hand them out to each unit of work in turn:
+///
+/// ```no_run
+/// use tracking_allocator::AllocationGroupPool;
+///
+/// let pool = AllocationGroupPool::with_capacity(1024).expect("failed to register allocation groups");
+///
+/// if let Some(mut token) = pool.checkout() {
+///     let _guard = token.enter();
+///     // Handle the request...
+/// }
+/// ```
+///
+/// Checked out tokens are returned to the pool when dropped. Any name set for the allocation group while it was checked
+/// out is cleared when it's returned, so that it doesn't carry over to the next checkout. As allocations are always
+/// attributed to the allocation group that made them, allocations that outlive a checkout are still deallocated against
+/// that same allocation group, even if it has since been checked out again.
+///
+/// `AllocationGroupPool` is cheaply cloneable, with all clones sharing the same tokens.
+#[derive(Clone)]
+pub struct AllocationGroupPool {
+    tokens: Arc<Mutex<Vec<AllocationGroupToken>>>,
+    capacity: usize,
+}
+
+impl AllocationGroupPool {
+    /// Creates a new `AllocationGroupPool` with `capacity` newly registered allocation groups.
+    ///
+    /// If registering `capacity` allocation groups would exceed the limit, `None` is returned, and no allocation groups
+    /// are registered, just as with [`AllocationGroupToken::register_many`].
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Option<Self> {
+        let tokens = AllocationGroupToken::register_many(capacity)?;
+        Some(Self {
+            tokens: Arc::new(Mutex::new(tokens)),
+            capacity,
+        })
+    }
+
+    /// Gets the total number of tokens in the pool, whether or not they're checked out.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Gets the number of tokens that are available to be checked out.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.lock_tokens(|tokens| tokens.len())
+    }
+
+    /// Checks out a token from the pool.
+    ///
+    /// If every token is already checked out, `None` is returned.
+    #[must_use]
+    pub fn checkout(&self) -> Option<PooledToken> {
+        let token = self.lock_tokens(Vec::pop)?;
+        Some(PooledToken {
+            token: Some(token),
+            pool: self.clone(),
+        })
+    }
+
+    fn lock_tokens<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Vec<AllocationGroupToken>) -> R,
+    {
+        with_suspended_allocation_group_if_available(|| {
+            f(&mut self.tokens.lock().unwrap_or_else(PoisonError::into_inner))
+        })
+    }
+}
+
+/// An allocation group token checked out from an [`AllocationGroupPool`].
+///
+/// Dereferences to the underlying [`AllocationGroupToken`], and returns it to the pool when dropped.
+pub struct PooledToken {
+    token: Option<AllocationGroupToken>,
+    pool: AllocationGroupPool,
+}
+
+impl Deref for PooledToken {
+    type Target = AllocationGroupToken;
+
+    fn deref(&self) -> &Self::Target {
+        self.token.as_ref().expect("token already returned")
+    }
+}
+
+impl DerefMut for PooledToken {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.token.as_mut().expect("token already returned")
+    }
+}
+
+impl Drop for PooledToken {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            crate::metadata::update_group_metadata(token.id(), |metadata| metadata.name = None);
+            self.pool.lock_tokens(|tokens| tokens.push(token));
+        }
+    }
+}
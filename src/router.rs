@@ -0,0 +1,465 @@
+use std::{alloc::Layout, ops::RangeInclusive};
+
+use crate::{AllocationGroupId, AllocationTracker};
+
+/// Filter that decides which allocation events are routed to a sink.
+///
+/// By default, a filter matches all allocation events. Filters can be narrowed down by object size, by allocation
+/// group, or both, in which case an allocation event must match all of the configured conditions.
+///
+/// Deallocation events are matched against the group ID the allocation originated from, rather than the current group
+/// ID, so that a sink sees the deallocation for every allocation that was routed to it.
+#[derive(Clone, Debug)]
+pub struct RouteFilter {
+    size_range: RangeInclusive<usize>,
+    groups: Option<Vec<AllocationGroupId>>,
+}
+
+impl RouteFilter {
+    /// Creates a new `RouteFilter` that matches all allocation events.
+    #[must_use]
+    pub fn any() -> Self {
+        Self {
+            size_range: 0..=usize::MAX,
+            groups: None,
+        }
+    }
+
+    /// Only matches allocation events whose object size falls within the given range.
+    #[must_use]
+    pub fn with_size_range(mut self, range: RangeInclusive<usize>) -> Self {
+        self.size_range = range;
+        self
+    }
+
+    /// Only matches allocation events for the given allocation groups.
+    #[must_use]
+    pub fn with_groups<I>(mut self, groups: I) -> Self
+    where
+        I: IntoIterator<Item = AllocationGroupId>,
+    {
+        self.groups = Some(groups.into_iter().collect());
+        self
+    }
+
+    #[inline]
+    fn matches(&self, object_size: usize, group_id: AllocationGroupId) -> bool {
+        self.size_range.contains(&object_size) && self.matches_group(group_id)
+    }
+
+    #[inline]
+    fn matches_group(&self, group_id: AllocationGroupId) -> bool {
+        self.groups
+            .as_ref()
+            .is_none_or(|groups| groups.contains(&group_id))
+    }
+}
+
+impl Default for RouteFilter {
+    fn default() -> Self {
+        Self::any()
+    }
+}
+
+struct Route {
+    filter: RouteFilter,
+    tracker: Box<dyn AllocationTracker + Send + Sync + 'static>,
+}
+
+/// Tracker that fans out allocation events to multiple sinks.
+///
+/// Each sink is a tracker paired with a [`RouteFilter`], and every allocation event is passed to each sink whose filter
+/// matches it, in the order the sinks were added. This makes it possible to, for example, count small allocations in
+/// one tracker while handing large allocations off to a more expensive tracker.
+///
+/// Routes are fixed once the router is installed as the global tracker, so dispatching an event never allocates.
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    /// Creates a new, empty `Router`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sink that receives all allocation events matching `filter`.
+    #[must_use]
+    pub fn route<T>(mut self, filter: RouteFilter, tracker: T) -> Self
+    where
+        T: AllocationTracker + Send + Sync + 'static,
+    {
+        self.routes.push(Route {
+            filter,
+            tracker: Box::new(tracker),
+        });
+        self
+    }
+}
+
+impl AllocationTracker for Router {
+    fn on_install(&self) {
+        for route in &self.routes {
+            route.tracker.on_install();
+        }
+    }
+
+    fn allocating(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        for route in &self.routes {
+            if route.filter.matches(object_layout.size(), group_id) {
+                route.tracker.allocating(object_layout, group_id);
+            }
+        }
+    }
+
+    fn allocation_failed(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        for route in &self.routes {
+            if route.filter.matches(object_layout.size(), group_id) {
+                route.tracker.allocation_failed(object_layout, group_id);
+            }
+        }
+    }
+
+    #[cfg(feature = "fault-injection")]
+    fn should_fail_allocation(&self, object_size: usize, group_id: AllocationGroupId) -> bool {
+        // Every matching sink is asked, even once one of them has decided to fail the allocation, so that sinks which
+        // count the allocations they see, such as to fail every Nth one, all see the same allocations.
+        let mut should_fail = false;
+        for route in &self.routes {
+            if route.filter.matches(object_size, group_id) {
+                should_fail |= route.tracker.should_fail_allocation(object_size, group_id);
+            }
+        }
+        should_fail
+    }
+
+    fn group_registered(&self, group_id: AllocationGroupId) {
+        // Registration isn't tied to any allocation, so only the allocation groups of a filter are considered.
+        for route in &self.routes {
+            if route.filter.matches_group(group_id) {
+                route.tracker.group_registered(group_id);
+            }
+        }
+    }
+
+    #[cfg(feature = "thread-exit-check")]
+    fn unbalanced_thread_exit(&self, group_id: AllocationGroupId) {
+        // Just like registration, a thread exiting isn't tied to any allocation, so only allocation groups are
+        // considered.
+        for route in &self.routes {
+            if route.filter.matches_group(group_id) {
+                route.tracker.unbalanced_thread_exit(group_id);
+            }
+        }
+    }
+
+    fn allocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_size, group_id) {
+                route
+                    .tracker
+                    .allocated(addr, object_size, wrapped_size, group_id);
+            }
+        }
+    }
+
+    fn allocated_with_layout(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_layout.size(), group_id) {
+                route
+                    .tracker
+                    .allocated_with_layout(addr, object_layout, wrapped_size, group_id);
+            }
+        }
+    }
+
+    fn allocated_zeroed(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_layout.size(), group_id) {
+                route
+                    .tracker
+                    .allocated_zeroed(addr, object_layout, wrapped_size, group_id);
+            }
+        }
+    }
+
+    fn deallocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_size, source_group_id) {
+                route.tracker.deallocated(
+                    addr,
+                    object_size,
+                    wrapped_size,
+                    source_group_id,
+                    current_group_id,
+                );
+            }
+        }
+    }
+
+    fn deallocated_with_layout(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_layout.size(), source_group_id) {
+                route.tracker.deallocated_with_layout(
+                    addr,
+                    object_layout,
+                    wrapped_size,
+                    source_group_id,
+                    current_group_id,
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "thread-context")]
+    fn allocated_on_thread(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+        thread: &std::thread::Thread,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_layout.size(), group_id) {
+                route.tracker.allocated_on_thread(
+                    addr,
+                    object_layout,
+                    wrapped_size,
+                    group_id,
+                    thread,
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "thread-context")]
+    fn deallocated_on_thread(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        thread: &std::thread::Thread,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_layout.size(), source_group_id) {
+                route.tracker.deallocated_on_thread(
+                    addr,
+                    object_layout,
+                    wrapped_size,
+                    source_group_id,
+                    current_group_id,
+                    thread,
+                );
+            }
+        }
+    }
+
+    fn deallocated_cross_group(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_size, source_group_id) {
+                route.tracker.deallocated_cross_group(
+                    addr,
+                    object_size,
+                    source_group_id,
+                    current_group_id,
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "track-lifetime")]
+    fn deallocated_with_age(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        age: u64,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_size, source_group_id) {
+                route
+                    .tracker
+                    .deallocated_with_age(addr, object_size, source_group_id, age);
+            }
+        }
+    }
+
+    #[cfg(feature = "event-sequence")]
+    fn allocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_size, group_id) {
+                route
+                    .tracker
+                    .allocated_with_sequence(addr, object_size, group_id, sequence);
+            }
+        }
+    }
+
+    #[cfg(feature = "event-sequence")]
+    fn deallocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        for route in &self.routes {
+            if route.filter.matches(object_size, source_group_id) {
+                route.tracker.deallocated_with_sequence(
+                    addr,
+                    object_size,
+                    source_group_id,
+                    current_group_id,
+                    sequence,
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "event-sequence")]
+    fn reallocated_with_sequence(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_size: usize,
+        new_object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        // Matched the same way as `reallocated`, so each sink sees the same kind of event with and without sequencing.
+        for route in &self.routes {
+            let old_matches = route.filter.matches(old_object_size, source_group_id);
+            let new_matches = route.filter.matches(new_object_size, current_group_id);
+
+            match (old_matches, new_matches) {
+                (true, true) => route.tracker.reallocated_with_sequence(
+                    old_addr,
+                    new_addr,
+                    old_object_size,
+                    new_object_size,
+                    source_group_id,
+                    current_group_id,
+                    sequence,
+                ),
+                (true, false) => route.tracker.deallocated_with_sequence(
+                    old_addr,
+                    old_object_size,
+                    source_group_id,
+                    current_group_id,
+                    sequence,
+                ),
+                (false, true) => route.tracker.allocated_with_sequence(
+                    new_addr,
+                    new_object_size,
+                    current_group_id,
+                    sequence,
+                ),
+                (false, false) => {}
+            }
+        }
+    }
+
+    fn reallocated(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_layout: Layout,
+        new_object_size: usize,
+        old_wrapped_size: usize,
+        new_wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        // SAFETY: The new size was valid for the alignment of the original layout, as the reallocation succeeded.
+        let new_object_layout = unsafe {
+            Layout::from_size_align_unchecked(new_object_size, old_object_layout.align())
+        };
+
+        // The original block and the reallocated block are matched separately, so a sink that only matches one of them
+        // sees the reallocation as a plain deallocation or allocation instead.
+        for route in &self.routes {
+            let old_matches = route
+                .filter
+                .matches(old_object_layout.size(), source_group_id);
+            let new_matches = route.filter.matches(new_object_size, current_group_id);
+
+            match (old_matches, new_matches) {
+                (true, true) => route.tracker.reallocated(
+                    old_addr,
+                    new_addr,
+                    old_object_layout,
+                    new_object_size,
+                    old_wrapped_size,
+                    new_wrapped_size,
+                    source_group_id,
+                    current_group_id,
+                ),
+                (true, false) => route.tracker.deallocated_with_layout(
+                    old_addr,
+                    old_object_layout,
+                    old_wrapped_size,
+                    source_group_id,
+                    current_group_id,
+                ),
+                (false, true) => route.tracker.allocated_with_layout(
+                    new_addr,
+                    new_object_layout,
+                    new_wrapped_size,
+                    current_group_id,
+                ),
+                (false, false) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    ptr, slice,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "track-lifetime")]
+use std::sync::atomic::AtomicU64;
+
+use crate::lifetime::Sequence;
+
+// Allocators never hand out null pointers, or pointers to the first page of the address space, so these addresses can
+// never collide with a live allocation.
+const EMPTY: usize = 0;
+const TOMBSTONE: usize = 1;
+
+// The maximum number of slots that are probed when looking up an address. Bounding this keeps lookups cheap even when
+// the table is almost full, at the cost of leaving some allocations untracked.
+const MAX_PROBES: usize = 64;
+
+// Fibonacci hashing constant, truncated to the pointer width.
+#[allow(clippy::cast_possible_truncation)]
+const FIBONACCI_MULTIPLIER: usize = 0x9E37_79B9_7F4A_7C15_u64 as usize;
+
+/// A single slot in the side table.
+pub(crate) struct Entry {
+    addr: AtomicUsize,
+    group_id: AtomicUsize,
+    #[cfg(feature = "track-lifetime")]
+    sequence: AtomicU64,
+}
+
+impl Entry {
+    /// Gets the raw group ID of the allocation in this slot.
+    pub fn group_id(&self) -> usize {
+        self.group_id.load(Ordering::Relaxed)
+    }
+
+    /// Sets the raw group ID of the allocation in this slot.
+    pub fn set_group_id(&self, raw_group_id: usize) {
+        self.group_id.store(raw_group_id, Ordering::Relaxed);
+    }
+
+    /// Gets the sequence number of the allocation in this slot.
+    #[allow(clippy::unused_self)]
+    pub fn sequence(&self) -> Sequence {
+        #[cfg(feature = "track-lifetime")]
+        return Sequence::from_raw(self.sequence.load(Ordering::Relaxed));
+
+        #[cfg(not(feature = "track-lifetime"))]
+        Sequence::default()
+    }
+
+    /// Sets the sequence number of the allocation in this slot.
+    #[allow(clippy::unused_self)]
+    pub fn set_sequence(&self, sequence: Sequence) {
+        #[cfg(feature = "track-lifetime")]
+        self.sequence.store(sequence.as_raw(), Ordering::Relaxed);
+
+        #[cfg(not(feature = "track-lifetime"))]
+        let _ = sequence;
+    }
+
+    /// Releases this slot so that it can be claimed by another allocation.
+    pub fn release(&self) {
+        self.addr.store(TOMBSTONE, Ordering::Release);
+    }
+}
+
+/// Fixed-capacity, lock-free map of allocation addresses to the raw group ID they were attributed to.
+///
+/// This is an open-addressing hash table with linear probing. Removed entries are left as tombstones, which are reused
+/// by later insertions, so the table never needs to be rehashed. The backing storage is allocated directly from the
+/// wrapped allocator the first time it's needed, and lives for the remainder of the process.
+///
+/// An address is only ever inserted, looked up, or removed by whoever owns the allocation at that address, so the only
+/// contention between threads is over which slot a given address ends up in, which is resolved when claiming the slot.
+pub(crate) struct SideTable {
+    capacity: usize,
+    entries: AtomicPtr<Entry>,
+}
+
+impl SideTable {
+    /// Creates a new `SideTable` that can hold at least `capacity` entries.
+    pub const fn new(capacity: usize) -> Self {
+        let capacity = if capacity < MAX_PROBES {
+            MAX_PROBES
+        } else {
+            capacity.next_power_of_two()
+        };
+
+        Self {
+            capacity,
+            entries: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Gets the capacity of the table.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Gets the entries of the table, allocating them from `allocator` if they haven't been allocated yet.
+    ///
+    /// If the entries cannot be allocated, `None` is returned.
+    pub fn entries<A: GlobalAlloc>(&self, allocator: &A) -> Option<&[Entry]> {
+        let mut entries_ptr = self.entries.load(Ordering::Acquire);
+        if entries_ptr.is_null() {
+            let layout = Layout::array::<Entry>(self.capacity).ok()?;
+
+            // SAFETY: The layout is never zero-sized, and an all-zeroes `Entry` is a valid, empty entry. The layout is
+            // for an array of `Entry`, so the allocation is suitably aligned for it.
+            #[allow(clippy::cast_ptr_alignment)]
+            let new_entries_ptr = unsafe { allocator.alloc_zeroed(layout) }.cast::<Entry>();
+            if new_entries_ptr.is_null() {
+                return None;
+            }
+
+            entries_ptr = match self.entries.compare_exchange(
+                ptr::null_mut(),
+                new_entries_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => new_entries_ptr,
+                Err(existing_entries_ptr) => {
+                    // Another thread beat us to it, so give back what we allocated and use theirs.
+                    //
+                    // SAFETY: We allocated `new_entries_ptr` with `layout` above, and never shared it.
+                    unsafe { allocator.dealloc(new_entries_ptr.cast::<u8>(), layout) };
+                    existing_entries_ptr
+                }
+            };
+        }
+
+        // SAFETY: Once set, the entries pointer always points to `capacity` initialized entries that are never freed.
+        Some(unsafe { slice::from_raw_parts(entries_ptr, self.capacity) })
+    }
+
+    /// Claims a slot for the given address.
+    ///
+    /// The claimed slot starts out with a raw group ID of zero. If no free slot can be found, `None` is returned.
+    pub fn claim<'a>(&self, entries: &'a [Entry], addr: usize) -> Option<&'a Entry> {
+        for entry in self.probe(entries, addr) {
+            let current = entry.addr.load(Ordering::Relaxed);
+            if (current == EMPTY || current == TOMBSTONE)
+                && entry
+                    .addr
+                    .compare_exchange(current, addr, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                entry.set_group_id(0);
+                return Some(entry);
+            }
+        }
+
+        None
+    }
+
+    /// Finds the slot holding the given address, if any.
+    pub fn find<'a>(&self, entries: &'a [Entry], addr: usize) -> Option<&'a Entry> {
+        for entry in self.probe(entries, addr) {
+            match entry.addr.load(Ordering::Acquire) {
+                current if current == addr => return Some(entry),
+                EMPTY => return None,
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Removes the given address from the table, returning the raw group ID it was attributed to, and its sequence
+    /// number.
+    ///
+    /// If the address isn't in the table, zero is returned, which is the raw group ID of an untracked allocation.
+    pub fn take(&self, entries: &[Entry], addr: usize) -> (usize, Sequence) {
+        self.find(entries, addr)
+            .map_or((0, Sequence::default()), |entry| {
+                let raw_group_id = entry.group_id();
+                let sequence = entry.sequence();
+                entry.release();
+                (raw_group_id, sequence)
+            })
+    }
+
+    /// Puts the given address back in the table, attributed to the given raw group ID and sequence number, after it was
+    /// taken out.
+    ///
+    /// This is for when an allocation turns out not to have gone away after all, such as when reallocating it fails. A
+    /// raw group ID of zero means the address wasn't in the table to begin with, so nothing is restored. If no free
+    /// slot can be found, the allocation is left untracked.
+    pub fn restore(&self, entries: &[Entry], addr: usize, raw_group_id: usize, sequence: Sequence) {
+        if raw_group_id == 0 {
+            return;
+        }
+
+        if let Some(entry) = self.claim(entries, addr) {
+            entry.set_group_id(raw_group_id);
+            entry.set_sequence(sequence);
+        }
+    }
+
+    fn probe<'a>(&self, entries: &'a [Entry], addr: usize) -> impl Iterator<Item = &'a Entry> {
+        // The low bits of an address are mostly zero due to alignment, so we scramble the address before using it to
+        // pick a starting slot, which keeps neighbouring allocations from clustering in the table. With Fibonacci
+        // hashing, the well-mixed bits of the product are the high ones, so the starting slot is taken from the top
+        // `log2(capacity)` bits. The capacity is always a power of two larger than one, so the shift never overflows.
+        let mask = self.capacity - 1;
+        let start = addr.wrapping_mul(FIBONACCI_MULTIPLIER)
+            >> (usize::BITS - self.capacity.trailing_zeros());
+        (0..MAX_PROBES).map(move |i| &entries[start.wrapping_add(i) & mask])
+    }
+}
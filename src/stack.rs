@@ -28,11 +28,16 @@ impl GroupStack {
         } else {
             self.slots
                 .last()
-                .cloned()
+                .copied()
                 .expect("self.slots cannot be empty")
         }
     }
 
+    /// Gets the allocation groups on the stack, from outermost to innermost.
+    pub fn groups(&self) -> &[AllocationGroupId] {
+        &self.slots
+    }
+
     /// Pushes an allocation group on to the stack, marking it as the active allocation group.
     pub fn push(&mut self, group: AllocationGroupId) {
         if self.slots.len() == self.slots.capacity() {
@@ -57,3 +62,14 @@ impl GroupStack {
             .expect("pop should not be callable when group stack is empty")
     }
 }
+
+#[cfg(feature = "thread-exit-check")]
+impl Drop for GroupStack {
+    fn drop(&mut self) {
+        // The stack is only ever dropped when the thread that owns it exits, at which point any allocation group still
+        // on the stack was entered but never exited, such as by a guard that was leaked.
+        if !self.slots.is_empty() {
+            crate::report_unbalanced_thread_exit(self.current());
+        }
+    }
+}
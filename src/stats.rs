@@ -0,0 +1,279 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, PoisonError, RwLock,
+    },
+};
+
+use crate::{token::with_suspended_allocation_group, AllocationGroupId, AllocationTracker};
+
+/// Number of shards that the per-group counters are spread across.
+const SHARDS: usize = 16;
+
+#[derive(Default)]
+struct GroupCounters {
+    allocations: AtomicUsize,
+    deallocations: AtomicUsize,
+    allocated_bytes: AtomicUsize,
+    deallocated_bytes: AtomicUsize,
+    live_bytes: AtomicUsize,
+    peak_live_bytes: AtomicUsize,
+}
+
+type Shard = RwLock<HashMap<AllocationGroupId, GroupCounters>>;
+
+/// Allocation statistics for a single allocation group.
+///
+/// Returned by [`StatsTracker::snapshot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GroupStats {
+    /// The allocation group these statistics are for.
+    pub group_id: AllocationGroupId,
+
+    /// Number of allocations made by the allocation group.
+    pub allocations: usize,
+
+    /// Number of deallocations of allocations that were made by the allocation group.
+    pub deallocations: usize,
+
+    /// Total bytes allocated by the allocation group.
+    pub allocated_bytes: usize,
+
+    /// Total bytes deallocated from allocations that were made by the allocation group.
+    pub deallocated_bytes: usize,
+
+    /// Number of bytes allocated by the allocation group that are still live.
+    ///
+    /// This is kept as a running total of its own, rather than derived from the byte totals, so that it never drops
+    /// below zero when the tracker sees the deallocation of an allocation it never saw being made, and always agrees
+    /// with `peak_live_bytes`.
+    pub live_bytes: usize,
+
+    /// The highest number of live bytes the allocation group has had at any one time.
+    ///
+    /// This only ever increases, even as the live bytes of the allocation group drop back down.
+    pub peak_live_bytes: usize,
+}
+
+impl GroupStats {
+    /// Gets the number of bytes allocated by the allocation group that are still live.
+    #[must_use]
+    pub fn live_bytes(&self) -> usize {
+        self.live_bytes
+    }
+
+    /// Gets the number of allocations made by the allocation group that are still live.
+    #[must_use]
+    pub fn live_allocations(&self) -> usize {
+        self.allocations.saturating_sub(self.deallocations)
+    }
+}
+
+/// Gets the change in live bytes of every allocation group between two snapshots.
+///
+/// Both snapshots are expected to come from [`StatsTracker::snapshot`] on the same tracker, with `before` taken at the
+/// start of the code region of interest and `after` at the end, so that whatever is left over is what the region still
+/// has allocated. Allocation groups present in only one of the snapshots are treated as having no live bytes in the
+/// other. Only allocation groups whose live bytes changed are returned, ordered by group ID:
+///
+/// ```no_run
+/// use tracking_allocator::{diff_live_bytes, AllocationRegistry, StatsTracker};
+///
+/// let stats = StatsTracker::new();
+/// AllocationRegistry::set_global_tracker(stats.clone()).expect("no other global tracker should be set");
+/// AllocationRegistry::enable_tracking();
+///
+/// let before = stats.snapshot();
+/// std::mem::forget(Vec::<u8>::with_capacity(64));
+/// let after = stats.snapshot();
+///
+/// for (group_id, delta) in diff_live_bytes(&before, &after) {
+///     println!("{}: {:+} live bytes", group_id, delta);
+/// }
+/// ```
+#[must_use]
+pub fn diff_live_bytes(
+    before: &[GroupStats],
+    after: &[GroupStats],
+) -> Vec<(AllocationGroupId, i64)> {
+    let mut deltas = BTreeMap::new();
+    for group in before {
+        let delta = deltas.entry(group.group_id).or_insert(0i64);
+        *delta = delta.saturating_sub(live_bytes_as_i64(group));
+    }
+    for group in after {
+        let delta = deltas.entry(group.group_id).or_insert(0i64);
+        *delta = delta.saturating_add(live_bytes_as_i64(group));
+    }
+
+    deltas
+        .into_iter()
+        .filter(|(_, delta)| *delta != 0)
+        .collect()
+}
+
+fn live_bytes_as_i64(group: &GroupStats) -> i64 {
+    i64::try_from(group.live_bytes()).unwrap_or(i64::MAX)
+}
+
+/// Tracker that keeps allocation statistics for every allocation group.
+///
+/// Allocations are counted against the allocation group that made them, and so are their deallocations, regardless of
+/// which allocation group is active when they happen, so that the live bytes of an allocation group stay balanced even
+/// when values are dropped elsewhere. Sizes are object sizes, as requested by the caller, rather than wrapped sizes.
+///
+/// `StatsTracker` is cheaply cloneable, with all clones sharing the same statistics, so that a clone can be installed
+/// as the global tracker while another is kept around to take snapshots from:
+///
+/// ```no_run
+/// use tracking_allocator::{AllocationRegistry, StatsTracker};
+///
+/// let stats = StatsTracker::new();
+/// AllocationRegistry::set_global_tracker(stats.clone()).expect("no other global tracker should be set");
+/// AllocationRegistry::enable_tracking();
+///
+/// for group in stats.snapshot() {
+///     println!("{}: {} live bytes", group.group_id, group.live_bytes());
+/// }
+/// ```
+///
+/// Counters are spread across a number of shards, keyed by group ID, and only ever updated atomically, so tracking an
+/// event only contends with other threads when an allocation group is seen for the very first time.
+///
+/// This also makes `StatsTracker` the way to export allocation statistics to a metrics system, such as the `metrics`
+/// facade or a Prometheus registry. Recording a metric typically means building labels, hashing a key, and looking it
+/// up in a registry, all of which allocate or lock, so doing it from within a tracker would add that cost to every
+/// single allocation. Instead, a background task can take a snapshot on an interval, and set a gauge or counter for
+/// each allocation group, labelled by [`AllocationRegistry::group_name`][crate::AllocationRegistry::group_name]. Only
+/// named allocation groups need to be exported, which keeps the number of distinct labels bounded:
+///
+/// ```no_run
+/// # use tracking_allocator::{AllocationRegistry, StatsTracker};
+/// # let stats = StatsTracker::new();
+/// for group in stats.snapshot() {
+///     if let Some(name) = AllocationRegistry::group_name(&group.group_id) {
+///         println!("allocations_total{{group=\"{}\"}} {}", name, group.allocations);
+///         println!("bytes_allocated_total{{group=\"{}\"}} {}", name, group.allocated_bytes);
+///         println!("bytes_live{{group=\"{}\"}} {}", name, group.live_bytes());
+///     }
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct StatsTracker {
+    shards: Arc<[Shard; SHARDS]>,
+}
+
+impl StatsTracker {
+    /// Creates a new `StatsTracker` with no statistics.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets a snapshot of the statistics of every allocation group that has been seen so far, ordered by group ID.
+    ///
+    /// Counters are read individually, so the statistics of an allocation group may be slightly inconsistent with each
+    /// other if allocations are happening concurrently.
+    ///
+    /// Any allocations made while taking the snapshot are not tracked.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<GroupStats> {
+        // Building the snapshot allocates while holding a shard's lock, and tracking those allocations could need to
+        // take that same lock for writing, if the active allocation group hasn't been seen yet and lives in that shard.
+        with_suspended_allocation_group(|| {
+            let mut snapshot = Vec::new();
+            for shard in self.shards.iter() {
+                let shard = shard.read().unwrap_or_else(PoisonError::into_inner);
+                snapshot.extend(shard.iter().map(|(group_id, counters)| GroupStats {
+                    group_id: *group_id,
+                    allocations: counters.allocations.load(Ordering::Relaxed),
+                    deallocations: counters.deallocations.load(Ordering::Relaxed),
+                    allocated_bytes: counters.allocated_bytes.load(Ordering::Relaxed),
+                    deallocated_bytes: counters.deallocated_bytes.load(Ordering::Relaxed),
+                    live_bytes: counters.live_bytes.load(Ordering::Relaxed),
+                    peak_live_bytes: counters.peak_live_bytes.load(Ordering::Relaxed),
+                }));
+            }
+
+            snapshot.sort_by_key(|a| a.group_id);
+            snapshot
+        })
+    }
+
+    fn with_counters<F>(&self, group_id: AllocationGroupId, f: F)
+    where
+        F: FnOnce(&GroupCounters),
+    {
+        let shard = &self.shards[group_id.as_usize().get() % SHARDS];
+
+        if let Some(counters) = shard
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&group_id)
+        {
+            f(counters);
+            return;
+        }
+
+        f(shard
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(group_id)
+            .or_default());
+    }
+}
+
+impl AllocationTracker for StatsTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        self.with_counters(group_id, |counters| {
+            counters.allocations.fetch_add(1, Ordering::Relaxed);
+            counters
+                .allocated_bytes
+                .fetch_add(object_size, Ordering::Relaxed);
+
+            // Live bytes are tracked separately from the totals, so that the peak is always derived from a single,
+            // consistent running total rather than from two counters that may be updated concurrently.
+            let live_bytes = counters
+                .live_bytes
+                .fetch_add(object_size, Ordering::Relaxed)
+                .wrapping_add(object_size);
+            counters
+                .peak_live_bytes
+                .fetch_max(live_bytes, Ordering::Relaxed);
+        });
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        self.with_counters(source_group_id, |counters| {
+            counters.deallocations.fetch_add(1, Ordering::Relaxed);
+            counters
+                .deallocated_bytes
+                .fetch_add(object_size, Ordering::Relaxed);
+
+            // A tracker can see the deallocation of an allocation that it never saw being made, such as one made before
+            // it replaced another tracker, so live bytes bottom out at zero rather than wrapping around, which would
+            // otherwise leave the peak stuck near `usize::MAX` from the next allocation on.
+            let _ = counters.live_bytes.fetch_update(
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+                |live_bytes| Some(live_bytes.saturating_sub(object_size)),
+            );
+        });
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    alloc::Layout,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        PoisonError, RwLock,
+    },
+};
+
+use crate::{AllocationGroupId, AllocationTracker};
+
+struct GroupState {
+    live_bytes: AtomicUsize,
+    armed: AtomicBool,
+}
+
+impl Default for GroupState {
+    fn default() -> Self {
+        Self {
+            live_bytes: AtomicUsize::new(0),
+            armed: AtomicBool::new(true),
+        }
+    }
+}
+
+/// Tracker that raises an alarm when the live bytes of an allocation group cross a limit.
+///
+/// `ThresholdTracker` wraps another tracker, passing every event through to it unchanged, while keeping a running count
+/// of the live bytes of each allocation group. Whenever an allocation takes an allocation group to `limit` live bytes
+/// or more, the callback is called with the group ID and its live bytes, which makes it possible to catch runaway
+/// allocation early:
+///
+/// ```no_run
+/// use tracking_allocator::{AllocationRegistry, NoopTracker, ThresholdTracker};
+///
+/// let tracker = ThresholdTracker::new(NoopTracker, 512 * 1024 * 1024, |group_id, live_bytes| {
+///     eprintln!("{} has {} live bytes", group_id, live_bytes);
+/// });
+/// AllocationRegistry::set_global_tracker(tracker).expect("no other global tracker should be set");
+/// AllocationRegistry::enable_tracking();
+/// ```
+///
+/// The callback is called once per crossing: it's only called again for the same allocation group once its live bytes
+/// have dropped back down to `limit` minus the hysteresis, which defaults to a tenth of the limit, and can be changed
+/// via [`with_hysteresis`][Self::with_hysteresis]. This keeps an allocation group that hovers around the limit from
+/// raising an alarm on every allocation.
+///
+/// Just like the rest of the tracker, the callback is called with tracking suspended, so any allocations it makes, such
+/// as to format a message, are not attributed to the allocation group that crossed the limit. Live bytes are counted
+/// against the allocation group that made each allocation, using object sizes, just like
+/// [`StatsTracker`][crate::StatsTracker].
+pub struct ThresholdTracker<T, F> {
+    inner: T,
+    limit: usize,
+    hysteresis: usize,
+    callback: F,
+    groups: RwLock<HashMap<AllocationGroupId, GroupState>>,
+}
+
+impl<T, F> ThresholdTracker<T, F>
+where
+    F: Fn(AllocationGroupId, usize),
+{
+    /// Creates a new `ThresholdTracker` that wraps `inner`, and calls `callback` whenever the live bytes of an
+    /// allocation group cross `limit`.
+    #[must_use]
+    pub fn new(inner: T, limit: usize, callback: F) -> Self {
+        Self {
+            inner,
+            limit,
+            hysteresis: limit / 10,
+            callback,
+            groups: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Sets how far the live bytes of an allocation group must drop below the limit before the callback can be called
+    /// for it again.
+    #[must_use]
+    pub fn with_hysteresis(mut self, hysteresis: usize) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    fn with_state<G>(&self, group_id: AllocationGroupId, f: G)
+    where
+        G: FnOnce(&GroupState),
+    {
+        if let Some(state) = self
+            .groups
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&group_id)
+        {
+            f(state);
+            return;
+        }
+
+        f(self
+            .groups
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(group_id)
+            .or_default());
+    }
+
+    fn grow(&self, group_id: AllocationGroupId, bytes: usize) {
+        self.with_state(group_id, |state| {
+            let live_bytes = state
+                .live_bytes
+                .fetch_add(bytes, Ordering::Relaxed)
+                .wrapping_add(bytes);
+
+            if live_bytes >= self.limit && state.armed.swap(false, Ordering::AcqRel) {
+                (self.callback)(group_id, live_bytes);
+            }
+        });
+    }
+
+    fn shrink(&self, group_id: AllocationGroupId, bytes: usize) {
+        // Deallocations of untracked allocations never counted towards any allocation group.
+        if group_id == AllocationGroupId::UNTRACKED {
+            return;
+        }
+
+        // Just like with `StatsTracker`, live bytes bottom out at zero rather than wrapping around, as the tracker can
+        // see the deallocation of an allocation that it never saw being made.
+        self.with_state(group_id, |state| {
+            let previous_live_bytes = state
+                .live_bytes
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live_bytes| {
+                    Some(live_bytes.saturating_sub(bytes))
+                })
+                .unwrap_or_else(|live_bytes| live_bytes);
+            let live_bytes = previous_live_bytes.saturating_sub(bytes);
+
+            if live_bytes <= self.limit.saturating_sub(self.hysteresis) {
+                state.armed.store(true, Ordering::Release);
+            }
+        });
+    }
+}
+
+impl<T, F> AllocationTracker for ThresholdTracker<T, F>
+where
+    T: AllocationTracker,
+    F: Fn(AllocationGroupId, usize),
+{
+    fn allocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        self.grow(group_id, object_size);
+        self.inner
+            .allocated(addr, object_size, wrapped_size, group_id);
+    }
+
+    fn allocated_with_layout(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        self.grow(group_id, object_layout.size());
+        self.inner
+            .allocated_with_layout(addr, object_layout, wrapped_size, group_id);
+    }
+
+    fn allocated_zeroed(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        self.grow(group_id, object_layout.size());
+        self.inner
+            .allocated_zeroed(addr, object_layout, wrapped_size, group_id);
+    }
+
+    fn deallocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        self.shrink(source_group_id, object_size);
+        self.inner.deallocated(
+            addr,
+            object_size,
+            wrapped_size,
+            source_group_id,
+            current_group_id,
+        );
+    }
+
+    fn deallocated_with_layout(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        self.shrink(source_group_id, object_layout.size());
+        self.inner.deallocated_with_layout(
+            addr,
+            object_layout,
+            wrapped_size,
+            source_group_id,
+            current_group_id,
+        );
+    }
+
+    #[cfg(feature = "thread-context")]
+    fn allocated_on_thread(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+        thread: &std::thread::Thread,
+    ) {
+        self.grow(group_id, object_layout.size());
+        self.inner
+            .allocated_on_thread(addr, object_layout, wrapped_size, group_id, thread);
+    }
+
+    #[cfg(feature = "thread-context")]
+    fn deallocated_on_thread(
+        &self,
+        addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        thread: &std::thread::Thread,
+    ) {
+        self.shrink(source_group_id, object_layout.size());
+        self.inner.deallocated_on_thread(
+            addr,
+            object_layout,
+            wrapped_size,
+            source_group_id,
+            current_group_id,
+            thread,
+        );
+    }
+
+    fn deallocated_cross_group(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        self.inner
+            .deallocated_cross_group(addr, object_size, source_group_id, current_group_id);
+    }
+
+    #[cfg(feature = "track-lifetime")]
+    fn deallocated_with_age(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        age: u64,
+    ) {
+        self.inner
+            .deallocated_with_age(addr, object_size, source_group_id, age);
+    }
+
+    #[cfg(feature = "event-sequence")]
+    fn allocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        self.inner
+            .allocated_with_sequence(addr, object_size, group_id, sequence);
+    }
+
+    #[cfg(feature = "event-sequence")]
+    fn deallocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        self.inner.deallocated_with_sequence(
+            addr,
+            object_size,
+            source_group_id,
+            current_group_id,
+            sequence,
+        );
+    }
+
+    #[cfg(feature = "event-sequence")]
+    fn reallocated_with_sequence(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_size: usize,
+        new_object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        self.inner.reallocated_with_sequence(
+            old_addr,
+            new_addr,
+            old_object_size,
+            new_object_size,
+            source_group_id,
+            current_group_id,
+            sequence,
+        );
+    }
+
+    fn reallocated(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_layout: Layout,
+        new_object_size: usize,
+        old_wrapped_size: usize,
+        new_wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        // Within a single allocation group, only the change in size is applied, so that growing a large allocation
+        // doesn't briefly drop the allocation group below the limit, and raise the alarm all over again. Otherwise, the
+        // original block is taken off of the allocation group that made it, and the reallocated block is attributed to
+        // the current allocation group.
+        let old_object_size = old_object_layout.size();
+        if source_group_id != current_group_id {
+            self.shrink(source_group_id, old_object_size);
+            self.grow(current_group_id, new_object_size);
+        } else if new_object_size >= old_object_size {
+            self.grow(current_group_id, new_object_size - old_object_size);
+        } else {
+            self.shrink(current_group_id, old_object_size - new_object_size);
+        }
+        self.inner.reallocated(
+            old_addr,
+            new_addr,
+            old_object_layout,
+            new_object_size,
+            old_wrapped_size,
+            new_wrapped_size,
+            source_group_id,
+            current_group_id,
+        );
+    }
+
+    fn allocating(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        self.inner.allocating(object_layout, group_id);
+    }
+
+    fn allocation_failed(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        self.inner.allocation_failed(object_layout, group_id);
+    }
+
+    #[cfg(feature = "fault-injection")]
+    fn should_fail_allocation(&self, object_size: usize, group_id: AllocationGroupId) -> bool {
+        self.inner.should_fail_allocation(object_size, group_id)
+    }
+
+    fn group_registered(&self, group_id: AllocationGroupId) {
+        self.inner.group_registered(group_id);
+    }
+
+    fn on_install(&self) {
+        self.inner.on_install();
+    }
+
+    #[cfg(feature = "thread-exit-check")]
+    fn unbalanced_thread_exit(&self, group_id: AllocationGroupId) {
+        self.inner.unbalanced_thread_exit(group_id);
+    }
+}
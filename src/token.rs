@@ -1,7 +1,11 @@
 use std::{
     cell::RefCell,
+    convert::TryFrom,
+    error, fmt,
+    mem::ManuallyDrop,
     num::NonZeroUsize,
-    sync::atomic::{AtomicUsize, Ordering},
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
 };
 
 use crate::{stack::GroupStack, util::PhantomNotSend};
@@ -12,33 +16,164 @@ thread_local! {
     /// Any allocations which occur on this thread will be associated with whichever token is
     /// present at the time of the allocation.
     pub(crate) static LOCAL_ALLOCATION_GROUP_STACK: RefCell<GroupStack> =
-        RefCell::new(GroupStack::new());
+        const { RefCell::new(GroupStack::new()) };
+}
+
+/// The hook called whenever the active allocation group changes on a thread, if one is set.
+///
+/// This holds a `fn(AllocationGroupId)`, type-erased so that it can be swapped atomically.
+static CONTEXT_CHANGE_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// The number of allocation groups that have been registered so far, not counting the root allocation group.
+static REGISTERED_GROUPS: AtomicUsize = AtomicUsize::new(0);
+
+/// Gets the number of allocation groups that have been registered so far, not counting the root allocation group.
+pub(crate) fn registered_group_count() -> usize {
+    REGISTERED_GROUPS.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_context_change_hook(hook: Option<fn(AllocationGroupId)>) {
+    let hook = hook.map_or(ptr::null_mut(), |hook| hook as *mut ());
+    CONTEXT_CHANGE_HOOK.store(hook, Ordering::Release);
+}
+
+fn notify_context_change(current: AllocationGroupId) {
+    let hook = CONTEXT_CHANGE_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: `CONTEXT_CHANGE_HOOK` is only ever set to null, or to a `fn(AllocationGroupId)`.
+        let hook = unsafe { std::mem::transmute::<*mut (), fn(AllocationGroupId)>(hook) };
+        with_suspended_allocation_group(|| hook(current));
+    }
 }
 
 fn push_group_to_stack(group: AllocationGroupId) {
     LOCAL_ALLOCATION_GROUP_STACK.with(|stack| stack.borrow_mut().push(group));
+    notify_context_change(group);
+}
+
+/// Pushes an allocation group on to the stack, unless it's already the active allocation group.
+///
+/// Returns `true` if the allocation group was pushed, and must be popped again later.
+fn enter_group(group: AllocationGroupId) -> bool {
+    let pushed = LOCAL_ALLOCATION_GROUP_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        if stack.current() == group {
+            false
+        } else {
+            stack.push(group);
+            true
+        }
+    });
+
+    if pushed {
+        notify_context_change(group);
+    }
+    pushed
 }
 
 fn pop_group_from_stack() -> AllocationGroupId {
-    LOCAL_ALLOCATION_GROUP_STACK.with(|stack| stack.borrow_mut().pop())
+    let (group, current) = LOCAL_ALLOCATION_GROUP_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let group = stack.pop();
+        (group, stack.current())
+    });
+    notify_context_change(current);
+    group
 }
 
 /// The identifier that uniquely identifiers an allocation group.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+///
+/// Group IDs are `Copy`, and hash, compare, and order as their raw integer value, so they can be used directly as keys
+/// in any kind of map. As registered group IDs are small, dense integers, handed out in order starting right after the
+/// root allocation group, trackers can also store per-group data in a vector indexed by
+/// [`as_usize`][AllocationGroupId::as_usize], which is what [`IdVecMap`][crate::IdVecMap] does.
+///
+/// With the `serde` feature enabled, group IDs serialize as their raw integer value, as returned by
+/// [`as_usize`][AllocationGroupId::as_usize].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 pub struct AllocationGroupId(NonZeroUsize);
 
 impl AllocationGroupId {
     /// Attempts to create an `AllocationGroupId` from a raw `usize`.
     ///
-    /// If the raw value is zero, `None` is returned.
-    pub(crate) fn from_raw(id: usize) -> Option<Self> {
+    /// This is the inverse of [`as_usize`][Self::as_usize], and is primarily useful for reloading group IDs that were
+    /// persisted elsewhere. If the raw value is zero, `None` is returned.
+    ///
+    /// No check is made that the group ID was ever handed out by [`AllocationGroupToken::register`]. Using a group ID
+    /// that was not is harmless, as there is simply no metadata, such as a name, associated with it, but anything
+    /// attributed to it is meaningless.
+    #[must_use]
+    pub fn from_raw(id: usize) -> Option<Self> {
         NonZeroUsize::new(id).map(Self)
     }
 }
 
+/// Formats the group ID as the name of its allocation group, if one has been set, or as `group#<id>` otherwise.
+///
+/// This renders the same label as [`AllocationRegistry::group_label`][crate::AllocationRegistry::group_label], but
+/// without allocating.
+///
+/// Looking up the name only takes a shared lock on the group metadata, so formatting group IDs from many threads at
+/// once, such as from within a tracker, never makes them wait on each other, only on a name being set at the same time.
+/// Where even that matters, the `Debug` format never looks up the name, and always renders the raw group ID.
+impl fmt::Display for AllocationGroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match crate::AllocationRegistry::group_name(self) {
+            Some(name) => f.pad(name),
+            None => write!(f, "group#{}", self.0),
+        }
+    }
+}
+
+impl TryFrom<usize> for AllocationGroupId {
+    type Error = InvalidGroupIdError;
+
+    fn try_from(id: usize) -> Result<Self, Self::Error> {
+        Self::from_raw(id).ok_or(InvalidGroupIdError { _sealed: () })
+    }
+}
+
+/// Returned if trying to convert zero into an [`AllocationGroupId`].
+#[derive(Debug)]
+pub struct InvalidGroupIdError {
+    _sealed: (),
+}
+
+impl fmt::Display for InvalidGroupIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("allocation group IDs cannot be zero")
+    }
+}
+
+impl error::Error for InvalidGroupIdError {}
+
 impl AllocationGroupId {
     /// The group ID used for allocations which are not made within a registered allocation group.
-    pub const ROOT: Self = Self(unsafe { NonZeroUsize::new_unchecked(1) });
+    pub const ROOT: Self = Self(match NonZeroUsize::new(1) {
+        Some(id) => id,
+        None => unreachable!(),
+    });
+
+    /// The group ID reported as the source of deallocations for allocations that were never tracked.
+    ///
+    /// This group ID is only ever observed when untracked deallocations are enabled via
+    /// [`AllocationRegistry::enable_untracked_deallocations`][crate::AllocationRegistry::enable_untracked_deallocations].
+    /// It is never handed out to a registered allocation group.
+    pub const UNTRACKED: Self = Self(match NonZeroUsize::new(usize::MAX) {
+        Some(id) => id,
+        None => unreachable!(),
+    });
+
+    /// The group ID that allocations made by the tracker itself are attributed to.
+    ///
+    /// This group ID is only ever used when observer accounting is enabled via
+    /// [`AllocationRegistry::enable_observer_accounting`][crate::AllocationRegistry::enable_observer_accounting]. It is
+    /// never handed out to a registered allocation group, nor is it ever passed to the tracker.
+    pub const OBSERVER: Self = Self(match NonZeroUsize::new(usize::MAX - 1) {
+        Some(id) => id,
+        None => unreachable!(),
+    });
 
     /// Gets the integer representation of this group ID.
     #[must_use]
@@ -46,20 +181,69 @@ impl AllocationGroupId {
         self.0
     }
 
+    /// The exclusive upper bound of the group IDs that can be handed out to registered allocation groups.
+    #[cfg(not(feature = "compact-header"))]
+    const REGISTERED_LIMIT: usize = AllocationGroupId::OBSERVER.0.get();
+
+    /// The exclusive upper bound of the group IDs that can be handed out to registered allocation groups.
+    ///
+    /// With the `compact-header` feature, group IDs are stored as a `u32`, with `u32::MAX` standing in for the observer
+    /// group ID, so registered group IDs must be below that as well.
+    #[cfg(feature = "compact-header")]
+    #[allow(clippy::cast_possible_truncation)]
+    const REGISTERED_LIMIT: usize = {
+        let observer = AllocationGroupId::OBSERVER.0.get();
+        if observer < u32::MAX as usize {
+            observer
+        } else {
+            u32::MAX as usize
+        }
+    };
+
     fn register() -> Option<AllocationGroupId> {
+        Self::register_many(1).map(|mut ids| ids.next().expect("bug: registered no group IDs"))
+    }
+
+    /// Registers `count` allocation group IDs at once, returning them in ascending order.
+    ///
+    /// The IDs are contiguous, and are handed out with a single update of the shared ID counter.
+    fn register_many(count: usize) -> Option<impl Iterator<Item = AllocationGroupId>> {
         static GROUP_ID: AtomicUsize = AtomicUsize::new(AllocationGroupId::ROOT.0.get() + 1);
-        static HIGHEST_GROUP_ID: AtomicUsize =
-            AtomicUsize::new(AllocationGroupId::ROOT.0.get() + 1);
 
-        let group_id = GROUP_ID.fetch_add(1, Ordering::Relaxed);
-        let highest_group_id = HIGHEST_GROUP_ID.fetch_max(group_id, Ordering::AcqRel);
+        // The counter only advances when the whole batch fits within the ID space, so a batch that's too large to ever
+        // be registered fails on its own, without using up the IDs that are left for later registrations.
+        let mut first_group_id = GROUP_ID.load(Ordering::Relaxed);
+        let end_group_id = loop {
+            let end_group_id = first_group_id
+                .checked_add(count)
+                .filter(|end_group_id| *end_group_id <= Self::REGISTERED_LIMIT)?;
+
+            match GROUP_ID.compare_exchange_weak(
+                first_group_id,
+                end_group_id,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break end_group_id,
+                Err(current_group_id) => first_group_id = current_group_id,
+            }
+        };
 
-        if group_id >= highest_group_id {
-            let group_id = NonZeroUsize::new(group_id).expect("bug: GROUP_ID overflowed");
-            Some(AllocationGroupId(group_id))
-        } else {
-            None
-        }
+        REGISTERED_GROUPS.fetch_add(count, Ordering::Relaxed);
+        Some((first_group_id..end_group_id).map(Self::from_registered))
+    }
+
+    /// Gets the IDs of every allocation group registered so far, in ascending order.
+    ///
+    /// Registered IDs are handed out contiguously, starting right after the root allocation group, and a failed
+    /// registration never uses up any IDs, so the registered IDs are always a contiguous range.
+    pub(crate) fn registered() -> impl Iterator<Item = AllocationGroupId> {
+        let first_group_id = AllocationGroupId::ROOT.0.get() + 1;
+        (first_group_id..first_group_id + registered_group_count()).map(Self::from_registered)
+    }
+
+    fn from_registered(group_id: usize) -> AllocationGroupId {
+        AllocationGroupId(NonZeroUsize::new(group_id).expect("bug: GROUP_ID overflowed"))
     }
 }
 
@@ -79,6 +263,7 @@ impl AllocationGroupId {
 /// [`AllocationGuard`] also tracks if another allocation group was active prior to entering, and ensures it is set back
 /// as the active allocation group when the guard is dropped.  This allows allocation groups to be nested within each
 /// other.
+#[derive(Debug)]
 pub struct AllocationGroupToken(AllocationGroupId);
 
 impl AllocationGroupToken {
@@ -87,18 +272,99 @@ impl AllocationGroupToken {
     /// Allocation groups use an internal identifier that is incremented atomically, and monotonically, when
     /// registration occurs.  This identifier, thus, has a limit based on the pointer size of the architecture. In other
     /// words, on 32-bit systems, a limit of 2^32 allocation groups can be registered before this identifier space is
-    /// exhausted.  On 64-bit systems, this limit is 2^64.
+    /// exhausted.  On 64-bit systems, this limit is 2^64, unless the `compact-header` feature is enabled, in which case
+    /// the limit is 2^32 there as well.
     ///
     /// If the number of registered allocation groups exceeds the limit, `None` is returned. This is a permanent state
     /// until the application exits. Otherwise, `Some` is returned.
+    #[must_use]
     pub fn register() -> Option<AllocationGroupToken> {
-        AllocationGroupId::register().map(AllocationGroupToken)
+        let id = AllocationGroupId::register()?;
+        crate::report_group_registered(id);
+        Some(AllocationGroupToken(id))
+    }
+
+    /// Gets a token for the allocation group with the given name, registering it if necessary.
+    ///
+    /// The first call with a given name registers a new allocation group, and names it via
+    /// [`AllocationRegistry::set_group_name`][crate::AllocationRegistry::set_group_name], while every later call with
+    /// the same name returns a token for that same allocation group. This lets separate parts of an application share
+    /// an allocation group without having to pass a token around.
+    ///
+    /// Group IDs are handed out in registration order, so they aren't stable across runs of an application. When
+    /// allocation groups need to be identified consistently across runs, such as when exporting metrics, reports should
+    /// use the name, which can be looked up from the group ID via
+    /// [`AllocationRegistry::group_name`][crate::AllocationRegistry::group_name], rather than the group ID itself.
+    ///
+    /// The name is kept, along with the allocation group it refers to, for the rest of the life of the process, as
+    /// allocation groups are never unregistered. Each distinct name costs a small, fixed amount of memory, so names
+    /// should come from a bounded set, such as the subsystems of an application, rather than being derived from
+    /// per-request data. To reuse allocation groups across short-lived units of work, use an
+    /// [`AllocationGroupPool`][crate::AllocationGroupPool] instead.
+    ///
+    /// If the allocation group hasn't been registered yet, and the number of registered allocation groups exceeds the
+    /// limit, `None` is returned, just as with [`register`][Self::register].
+    #[must_use]
+    pub fn register_named(name: &'static str) -> Option<AllocationGroupToken> {
+        let (id, registered) = crate::metadata::get_or_register_named_group(name, || {
+            let id = AllocationGroupId::register()?;
+            crate::metadata::update_group_metadata(id, |metadata| metadata.name = Some(name));
+            Some(id)
+        })?;
+        if registered {
+            crate::report_group_registered(id);
+        }
+        Some(AllocationGroupToken(id))
+    }
+
+    /// Registers an allocation group token as a child of another allocation group.
+    ///
+    /// The parent is recorded purely for reporting, such as rolling up the allocations of each connection of a server
+    /// into the server itself, and can be retrieved via
+    /// [`AllocationRegistry::group_parent`][crate::AllocationRegistry::group_parent]. It has no effect on attribution:
+    /// allocations are only ever attributed to whichever allocation group is active, and entering the child doesn't
+    /// enter the parent. The parent is recorded before the tracker is told about the new allocation group.
+    ///
+    /// The parent is kept for the rest of the life of the process, as allocation groups are never unregistered, so
+    /// every allocation group registered this way costs a small, fixed amount of memory, on top of using up a group ID.
+    /// To reuse allocation groups across short-lived units of work, use an
+    /// [`AllocationGroupPool`][crate::AllocationGroupPool] instead.
+    ///
+    /// If the number of registered allocation groups exceeds the limit, `None` is returned, just as with
+    /// [`register`][Self::register].
+    #[must_use]
+    pub fn register_with_parent(parent: &AllocationGroupToken) -> Option<AllocationGroupToken> {
+        let id = AllocationGroupId::register()?;
+        crate::metadata::update_group_metadata(id, |metadata| metadata.parent = Some(parent.id()));
+        crate::report_group_registered(id);
+        Some(AllocationGroupToken(id))
+    }
+
+    /// Registers `count` allocation group tokens at once.
+    ///
+    /// This is cheaper than calling [`register`][Self::register] in a loop, such as when registering a known set of
+    /// allocation groups at startup. The tokens are returned in ascending order of their IDs, which are contiguous: the
+    /// ID of each token is one more than the ID of the token before it.
+    ///
+    /// If registering `count` allocation groups would exceed the limit, or there isn't enough memory to hold `count`
+    /// tokens, `None` is returned, and no tokens are registered. Any IDs that are left can still be registered
+    /// afterwards, such as by a smaller batch, or by [`register`][Self::register].
+    #[must_use]
+    pub fn register_many(count: usize) -> Option<Vec<AllocationGroupToken>> {
+        // Room for the tokens is made before registering any IDs, so that failing to allocate it doesn't use them up.
+        let mut tokens = Vec::new();
+        tokens.try_reserve_exact(count).ok()?;
+        tokens.extend(AllocationGroupId::register_many(count)?.map(|id| {
+            crate::report_group_registered(id);
+            AllocationGroupToken(id)
+        }));
+        Some(tokens)
     }
 
     /// Gets the ID associated with this allocation group.
     #[must_use]
     pub fn id(&self) -> AllocationGroupId {
-        self.0.clone()
+        self.0
     }
 
     #[cfg(feature = "tracing-compat")]
@@ -109,10 +375,33 @@ impl AllocationGroupToken {
     /// Enters the allocation group, marking it as the active allocation group on this thread.
     ///
     /// If another allocation group is currently active, it is replaced, and restored either when this allocation guard
-    /// is dropped, or when [`AllocationGuard::exit`] is called.
+    /// is dropped, or when [`AllocationGuard::exit`] is called. If this allocation group is already active, entering it
+    /// again does nothing, and neither does dropping or exiting the returned guard.
     pub fn enter(&mut self) -> AllocationGuard<'_> {
         AllocationGuard::enter(self)
     }
+
+    /// Enters the allocation group by reference, marking it as the active allocation group on this thread.
+    ///
+    /// This behaves exactly like [`enter`][AllocationGroupToken::enter], but only borrows the token immutably, so the
+    /// same token can be entered again, such as on every iteration of a loop, without first having to exit the guard
+    /// to get the token back. Entering a group that is already the active allocation group is a no-op, and the
+    /// returned guard leaves the allocation group active when dropped.
+    #[must_use]
+    pub fn enter_ref(&self) -> AllocationGuard<'_> {
+        AllocationGuard::enter(self)
+    }
+
+    /// Runs `f` with the allocation group entered, returning the result of `f`.
+    ///
+    /// The previously active allocation group is restored when `f` returns, including when it unwinds.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let _guard = self.enter_ref();
+        f()
+    }
 }
 
 #[cfg(feature = "tracing-compat")]
@@ -123,6 +412,10 @@ impl AllocationGroupToken {
     /// When the span is entered or exited, the allocation group will also transition from inactive to active, and vise
     /// versa.  In effect, all allocations that occur while the span is entered will be associated with the allocation
     /// group.
+    ///
+    /// # Panics
+    ///
+    /// Will not panic in practice: the token is only ever consumed once, by the dispatcher that owns the span.
     pub fn attach_to_span(self, span: &tracing::Span) {
         use crate::tracing::WithAllocationGroup;
 
@@ -139,6 +432,28 @@ impl AllocationGroupToken {
     }
 }
 
+/// Spawns a new thread with the given allocation group entered for the lifetime of the thread.
+///
+/// This mirrors [`std::thread::spawn`], but enters the allocation group as the very first thing on the new thread, and
+/// only exits it once `f` returns, so that every allocation made by `f` is attributed to the allocation group without
+/// needing to enter it anywhere else. As [`AllocationGuard`] cannot be sent across threads, the token itself is moved
+/// to the new thread, where the guard is created.
+///
+/// Allocations made by the standard library when setting up and tearing down the thread, outside of `f`, are not
+/// attributed to the allocation group. Neither are allocations made by any threads that `f` spawns in turn, as
+/// allocation groups are entered per thread, unless those are spawned with `spawn_in_group` as well.
+///
+/// # Panics
+///
+/// Panics if the operating system fails to create the thread, just like [`std::thread::spawn`].
+pub fn spawn_in_group<F, T>(token: AllocationGroupToken, f: F) -> std::thread::JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    std::thread::spawn(move || token.with(f))
+}
+
 /// Guard that updates the current thread to track allocations for the associated allocation group.
 ///
 /// ## Drop behavior
@@ -154,9 +469,16 @@ impl AllocationGroupToken {
 /// await point or when simply sending objects to another thread, you must first [`exit`][exit] the guard and move the
 /// resulting [`AllocationGroupToken`].  Once on the new thread, you can then reacquire the guard.
 ///
+/// The guard is `Sync`, however, as a shared reference to it only gives access to the ID of its allocation group, which
+/// is safe to read from any thread.
+///
 /// [exit]: AllocationGuard::exit
 pub struct AllocationGuard<'token> {
-    token: &'token mut AllocationGroupToken,
+    token: &'token AllocationGroupToken,
+
+    /// Whether the allocation group was pushed on to the stack when entered, which is not the case when it was
+    /// already the active allocation group.
+    pushed: bool,
 
     /// ```compile_fail
     /// use tracking_allocator::AllocationGuard;
@@ -168,17 +490,24 @@ pub struct AllocationGuard<'token> {
 }
 
 impl<'token> AllocationGuard<'token> {
-    pub(crate) fn enter(token: &'token mut AllocationGroupToken) -> Self {
-        // Push this group onto the stack.
-        push_group_to_stack(token.id());
+    pub(crate) fn enter(token: &'token AllocationGroupToken) -> Self {
+        // Push this group onto the stack. If it's already the active allocation group, such as when entering the same
+        // token again by reference, there's nothing to do, and nothing to restore when exiting.
+        let pushed = enter_group(token.id());
 
         Self {
             token,
+            pushed,
             _ns: PhantomNotSend::default(),
         }
     }
 
     fn exit_inner(&mut self) {
+        // Exiting explicitly consumes the guard, which then gets dropped, so we make sure to only ever pop once.
+        if !std::mem::replace(&mut self.pushed, false) {
+            return;
+        }
+
         #[allow(unused_variables)]
         let current = pop_group_from_stack();
         debug_assert_eq!(
@@ -188,19 +517,103 @@ impl<'token> AllocationGuard<'token> {
         );
     }
 
+    /// Gets the ID of the allocation group this guard has entered.
+    ///
+    /// This is the same group ID that is given to the tracker for allocations made while this guard is active.
+    #[must_use]
+    pub fn id(&self) -> AllocationGroupId {
+        self.token.id()
+    }
+
     /// Exits the allocation group, restoring the previously active allocation group on this thread.
     pub fn exit(mut self) {
         self.exit_inner();
     }
+
+    /// Suspends the allocation group without restoring the previously active allocation group.
+    ///
+    /// While suspended, allocations on this thread are attributed to the root allocation group, rather than to this
+    /// allocation group or to whichever allocation group was active before this guard was entered. The allocation group
+    /// can be made active again, in the same position, by calling [`SuspendedAllocationGuard::resume`].
+    ///
+    /// ## Interaction with nested allocation groups
+    ///
+    /// The suspended guard takes the place of this guard: dropping it restores the previously active allocation group,
+    /// exactly as dropping this guard would have. Allocation groups can be entered while suspended, but they must be
+    /// exited before the suspended guard is resumed or dropped, just as with any other nested allocation group.
+    #[must_use]
+    pub fn suspend(self) -> SuspendedAllocationGuard<'token> {
+        let mut guard = ManuallyDrop::new(self);
+        guard.exit_inner();
+        push_group_to_stack(AllocationGroupId::ROOT);
+
+        // SAFETY: `guard` is never dropped, so the token reference is only ever moved out of it once, here.
+        let token = unsafe { ptr::read(ptr::addr_of!(guard.token)) };
+
+        SuspendedAllocationGuard {
+            token,
+            _ns: PhantomNotSend::default(),
+        }
+    }
 }
 
-impl<'token> Drop for AllocationGuard<'token> {
+impl Drop for AllocationGuard<'_> {
     fn drop(&mut self) {
         self.exit_inner();
     }
 }
 
-/// Unmanaged allocation group token used specifically with `tracing`.
+/// Guard for an allocation group that has been temporarily suspended.
+///
+/// Created by [`AllocationGuard::suspend`]. While this guard is held, allocations on the current thread are attributed
+/// to the root allocation group. Calling [`resume`][SuspendedAllocationGuard::resume] makes the allocation group active
+/// again, while dropping this guard restores the allocation group that was active before the original guard was
+/// entered.
+///
+/// Like [`AllocationGuard`], this guard is `!Send`, as it tracks state that is local to the current thread.
+pub struct SuspendedAllocationGuard<'token> {
+    token: &'token AllocationGroupToken,
+
+    /// ```compile_fail
+    /// use tracking_allocator::SuspendedAllocationGuard;
+    /// trait AssertSend: Send {}
+    ///
+    /// impl AssertSend for SuspendedAllocationGuard {}
+    /// ```
+    _ns: PhantomNotSend,
+}
+
+impl<'token> SuspendedAllocationGuard<'token> {
+    fn exit_inner() {
+        #[allow(unused_variables)]
+        let current = pop_group_from_stack();
+        debug_assert_eq!(
+            current,
+            AllocationGroupId::ROOT,
+            "popped group from stack but got unexpected group"
+        );
+    }
+
+    /// Resumes the allocation group, marking it as the active allocation group on this thread once again.
+    #[must_use]
+    pub fn resume(self) -> AllocationGuard<'token> {
+        let guard = ManuallyDrop::new(self);
+        Self::exit_inner();
+
+        // SAFETY: `guard` is never dropped, so the token reference is only ever moved out of it once, here.
+        let token = unsafe { ptr::read(ptr::addr_of!(guard.token)) };
+
+        AllocationGuard::enter(token)
+    }
+}
+
+impl Drop for SuspendedAllocationGuard<'_> {
+    fn drop(&mut self) {
+        Self::exit_inner();
+    }
+}
+
+/// Unmanaged allocation group token used specifically with `tracing`, and by the C API.
 ///
 /// ## Safety
 ///
@@ -213,12 +626,15 @@ impl<'token> Drop for AllocationGuard<'token> {
 ///
 /// Thus, we build off of that invariant, and use this stripped down token to manually enter and exit the allocation
 /// group in a specialized `tracing_subscriber` layer that we control.
-#[cfg(feature = "tracing-compat")]
+///
+/// The C API has no such guarantees, and so simply documents that callers must exit allocation groups on the same
+/// thread that they entered them on.
+#[cfg(any(feature = "tracing-compat", feature = "capi"))]
 pub(crate) struct UnsafeAllocationGroupToken {
     id: AllocationGroupId,
 }
 
-#[cfg(feature = "tracing-compat")]
+#[cfg(any(feature = "tracing-compat", feature = "capi"))]
 impl UnsafeAllocationGroupToken {
     /// Creates a new `UnsafeAllocationGroupToken`.
     pub fn new(id: AllocationGroupId) -> Self {
@@ -232,7 +648,7 @@ impl UnsafeAllocationGroupToken {
     ///
     /// Functionally equivalent to [`AllocationGroupToken::enter`].
     pub fn enter(&mut self) {
-        push_group_to_stack(self.id.clone());
+        push_group_to_stack(self.id);
     }
 
     /// Exits the allocation group, restoring the previously active allocation group on this thread.
@@ -248,6 +664,70 @@ impl UnsafeAllocationGroupToken {
     }
 }
 
+/// Gets the ID of the allocation group that allocations on the current thread are currently attributed to.
+///
+/// When no allocation group has been entered, this is [`AllocationGroupId::ROOT`]. `None` is returned when allocations
+/// on the current thread aren't being attributed to any allocation group at all, such as when called from within the
+/// tracker itself, or while the thread is being torn down.
+///
+/// This does not allocate, and is cheap enough to call frequently, such as when annotating log messages.
+#[must_use]
+pub fn current_allocation_group() -> Option<AllocationGroupId> {
+    LOCAL_ALLOCATION_GROUP_STACK
+        .try_with(|stack| stack.try_borrow().ok().map(|stack| stack.current()))
+        .ok()
+        .flatten()
+}
+
+/// Gets the allocation groups that are currently entered on the current thread, from outermost to innermost.
+///
+/// The last group is the one that allocations are currently attributed to, as returned by [`current_allocation_group`].
+/// When no allocation group has been entered, the returned stack is empty. While an [`AllocationGuard`] is suspended,
+/// its place in the stack is taken by [`AllocationGroupId::ROOT`].
+///
+/// This is intended for debugging unexpected attribution, and allocates the returned vector on every call, so it
+/// should not be called on hot paths. The stack is empty when called from within the tracker itself.
+#[must_use]
+pub fn active_group_stack() -> Vec<AllocationGroupId> {
+    LOCAL_ALLOCATION_GROUP_STACK
+        .try_with(|stack| {
+            stack
+                .try_borrow()
+                .map(|stack| stack.groups().to_vec())
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+/// Runs the given closure with tracking suspended on the current thread.
+///
+/// Allocations and deallocations made by `f` are not attributed to any allocation group, and are never passed to the
+/// tracker, which makes this useful for hot sections that shouldn't show up in the tracked data, such as building a
+/// report about that data. The active allocation group is restored once `f` returns, or if it panics.
+///
+/// This is the same as [`AllocationRegistry::untracked`][crate::AllocationRegistry::untracked]. As with tracker
+/// callbacks, allocation groups cannot be entered or exited from within `f`, and attempting to do so will panic.
+pub fn suspend_tracking<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    with_suspended_allocation_group(f)
+}
+
+/// Gets the innermost allocation group that has been entered on the current thread, if any.
+#[cfg(feature = "capi")]
+pub(crate) fn innermost_entered_group() -> Option<AllocationGroupId> {
+    LOCAL_ALLOCATION_GROUP_STACK
+        .try_with(|stack| {
+            stack
+                .try_borrow()
+                .ok()
+                .and_then(|stack| stack.groups().last().copied())
+        })
+        .ok()
+        .flatten()
+}
+
 /// Calls `f` after suspending the active allocation group, if it was not already suspended.
 ///
 /// If the active allocation group is not currently suspended, then `f` is called, after suspending it, with a reference
@@ -297,3 +777,26 @@ where
         },
     )
 }
+
+/// Calls `f` after suspending the active allocation group, if the local allocation group stack is still available.
+///
+/// In contrast to `with_suspended_allocation_group`, this method doesn't panic when called after the local allocation
+/// group stack has been destroyed, such as from a thread local destructor while the thread is exiting, including the
+/// one that reports unbalanced thread exits. `f` is then called as is: allocations on the thread can no longer be
+/// tracked by that point, so there is nothing left to suspend.
+pub(crate) fn with_suspended_allocation_group_if_available<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let mut f = Some(f);
+    let result = LOCAL_ALLOCATION_GROUP_STACK.try_with(|stack| {
+        let _result = stack.try_borrow_mut();
+        f.take().map(|f| f())
+    });
+
+    match (result, f) {
+        (Ok(Some(result)), _) => result,
+        (_, Some(f)) => f(),
+        _ => unreachable!("f is only taken when the local allocation group stack is available"),
+    }
+}
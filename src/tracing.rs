@@ -4,6 +4,7 @@ use tracing::{Dispatch, Id, Subscriber};
 use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
 
 use crate::token::UnsafeAllocationGroupToken;
+use crate::{AllocationGroupId, AllocationTracker};
 
 pub(crate) struct WithAllocationGroup {
     with_allocation_group: fn(&Dispatch, &Id, UnsafeAllocationGroupToken),
@@ -25,6 +26,36 @@ impl WithAllocationGroup {
 ///
 /// More information on using this layer can be found in the examples, or directly in the
 /// `tracing_subscriber` docs, found [here][tracing_subscriber::layer].
+///
+/// ## Composing with other layers
+///
+/// [`AllocationLayer`] is a regular layer, and is added to a subscriber stack like any other layer. It must be part of
+/// the subscriber that is active when [`AllocationGroupToken::attach_to_span`][attach] is called, as that is how the
+/// allocation group finds its way to the span:
+///
+/// ```
+/// use tracing_subscriber::{layer::SubscriberExt, Registry};
+/// use tracking_allocator::{AllocationGroupToken, AllocationLayer};
+///
+/// let subscriber = Registry::default().with(AllocationLayer::new());
+/// tracing::subscriber::set_global_default(subscriber).expect("failed to install tracing subscriber");
+///
+/// let token = AllocationGroupToken::register().expect("failed to register allocation group");
+/// let span = tracing::info_span!("work");
+/// token.attach_to_span(&span);
+///
+/// let _entered = span.enter();
+/// // Allocations here are attributed to the allocation group.
+/// ```
+///
+/// Layers are notified of a span being entered or exited in the order they were added to the subscriber stack, for
+/// both entering and exiting. This means that any allocations made by layers added _after_ [`AllocationLayer`] while
+/// handling a span being entered are attributed to the span's allocation group, while allocations they make while
+/// handling the span being exited are not. For layers added _before_ [`AllocationLayer`], the opposite is true. If
+/// other layers allocate when spans are entered or exited, and those allocations should not be attributed to the
+/// allocation group, they should be added before [`AllocationLayer`], and should only allocate when spans are entered.
+///
+/// [attach]: crate::AllocationGroupToken::attach_to_span
 #[cfg_attr(docsrs, doc(cfg(feature = "tracing-compat")))]
 pub struct AllocationLayer<S> {
     ctx: WithAllocationGroup,
@@ -109,3 +140,68 @@ where
         AllocationLayer::new()
     }
 }
+
+/// [`TracingTracker`] is a tracker that emits a `TRACE`-level [`tracing`] event for every allocation and deallocation.
+///
+/// This provides basic instrumentation without writing a tracker, with events recorded under the `tracking_allocator`
+/// target, and carrying the address, sizes, and group IDs of each allocation as fields. Group IDs are recorded as their
+/// raw integer value.
+///
+/// ## Recursion
+///
+/// Emitting an event will usually allocate, but as with any other tracker, allocations made while the tracker is being
+/// called are never tracked, so emitting events can't recurse back into the tracker. Likewise, `tracing` itself never
+/// dispatches an event to a subscriber that's already handling an event on the same thread, so allocations made by the
+/// subscriber while handling an event don't cause it to be re-entered either.
+///
+/// As with any tracker, allocations made by the subscriber while it holds a lock, which it then tries to take again
+/// when handling an event, will deadlock. This is not an issue with the subscribers provided by `tracing-subscriber`.
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing-compat")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TracingTracker;
+
+impl TracingTracker {
+    /// Creates a new [`TracingTracker`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AllocationTracker for TracingTracker {
+    fn allocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        tracing::trace!(
+            target: "tracking_allocator",
+            addr,
+            object_size,
+            wrapped_size,
+            group_id = group_id.as_usize().get(),
+            "allocated",
+        );
+    }
+
+    fn deallocated(
+        &self,
+        addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        tracing::trace!(
+            target: "tracking_allocator",
+            addr,
+            object_size,
+            wrapped_size,
+            source_group_id = source_group_id.as_usize().get(),
+            current_group_id = current_group_id.as_usize().get(),
+            "deallocated",
+        );
+    }
+}
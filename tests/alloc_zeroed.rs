@@ -0,0 +1,89 @@
+//! Zeroed allocations should be reported via `allocated_zeroed`, attributed to the active allocation group, and hand
+//! back memory that is actually zeroed.
+
+use std::{
+    alloc::{Layout, System},
+    sync::Mutex,
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const BUFFER_SIZE: usize = 40_000;
+
+static ALLOCATED: Mutex<Vec<(bool, AllocationGroupId)>> = Mutex::new(Vec::new());
+static DEALLOCATED: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+
+struct ZeroedTracker;
+
+impl AllocationTracker for ZeroedTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == BUFFER_SIZE {
+            ALLOCATED.lock().unwrap().push((false, group_id));
+        }
+    }
+
+    fn allocated_zeroed(
+        &self,
+        _addr: usize,
+        object_layout: Layout,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_layout.size() == BUFFER_SIZE {
+            ALLOCATED.lock().unwrap().push((true, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == BUFFER_SIZE {
+            DEALLOCATED.lock().unwrap().push(source_group_id);
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(ZeroedTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut local_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = local_token.id();
+
+    let guard = local_token.enter();
+    let zeroed = vec![0u8; BUFFER_SIZE];
+    let regular = Vec::<u8>::with_capacity(BUFFER_SIZE);
+    drop(guard);
+
+    assert!(zeroed.iter().all(|b| *b == 0));
+    drop(zeroed);
+    drop(regular);
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *ALLOCATED.lock().unwrap(),
+        vec![(true, group_id), (false, group_id)]
+    );
+    assert_eq!(*DEALLOCATED.lock().unwrap(), vec![group_id, group_id]);
+}
@@ -0,0 +1,100 @@
+//! The pre-allocation hook should see every allocation attempt, with the layout being allocated and the allocation
+//! group making it, before the allocation itself is tracked, including reallocations, and allocations that fail.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::Mutex,
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself. The huge size leaves no room for the trailer, so allocating it always fails.
+const SIZE: usize = 2_011;
+const GROWN_SIZE: usize = 4_021;
+const HUGE_SIZE: usize = isize::MAX as usize;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    Allocating(usize, usize, AllocationGroupId),
+    Allocated(usize, AllocationGroupId),
+}
+
+static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+struct PeekTracker;
+
+impl AllocationTracker for PeekTracker {
+    fn allocating(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        if [SIZE, GROWN_SIZE, HUGE_SIZE].contains(&object_layout.size()) {
+            // Allocations made by the hook itself are never passed back to the tracker.
+            EVENTS.lock().unwrap().push(Event::Allocating(
+                object_layout.size(),
+                object_layout.align(),
+                group_id,
+            ));
+            drop(Vec::<u8>::with_capacity(SIZE));
+        }
+    }
+
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if [SIZE, GROWN_SIZE, HUGE_SIZE].contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(Event::Allocated(object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+#[test]
+fn test() {
+    EVENTS.lock().unwrap().reserve(16);
+    AllocationRegistry::set_global_tracker(PeekTracker)
+        .expect("no other global tracker should be set");
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    AllocationRegistry::enable_tracking();
+    token.with(|| {
+        let mut buf = Vec::<u8>::with_capacity(SIZE);
+        buf.reserve_exact(GROWN_SIZE);
+        drop(buf);
+
+        let huge_layout = Layout::from_size_align(HUGE_SIZE, 1).unwrap();
+        assert!(unsafe { ALLOCATOR.alloc_zeroed(huge_layout) }.is_null());
+    });
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            Event::Allocating(SIZE, 1, group_id),
+            Event::Allocated(SIZE, group_id),
+            Event::Allocating(GROWN_SIZE, 1, group_id),
+            Event::Allocated(GROWN_SIZE, group_id),
+            Event::Allocating(HUGE_SIZE, 1, group_id),
+        ]
+    );
+}
@@ -0,0 +1,103 @@
+//! When the wrapped allocator runs out of memory, the tracker should learn the layout and allocation group of the
+//! failed allocation before the allocation error handler aborts the process.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    env,
+    io::Write,
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+// Fails every allocation of at least `FAILING_SIZE` bytes, as if memory had run out.
+struct ExhaustedAllocator;
+
+const FAILING_SIZE: usize = 1 << 30;
+
+unsafe impl GlobalAlloc for ExhaustedAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= FAILING_SIZE {
+            return std::ptr::null_mut();
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Allocator<ExhaustedAllocator> = Allocator::from_allocator(ExhaustedAllocator);
+
+const CHILD_ENV_VAR: &str = "TRACKING_ALLOCATOR_ALLOCATION_FAILED_CHILD";
+
+static EXPECTED_GROUP_ID: AtomicUsize = AtomicUsize::new(0);
+
+struct FailureTracker;
+
+impl AllocationTracker for FailureTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn allocation_failed(&self, object_layout: Layout, group_id: AllocationGroupId) {
+        let expected = group_id.as_usize().get() == EXPECTED_GROUP_ID.load(Ordering::SeqCst);
+        let _ = writeln!(
+            std::io::stderr(),
+            "allocation of {} bytes failed in expected group: {}",
+            object_layout.size(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test() {
+    if env::var_os(CHILD_ENV_VAR).is_some() {
+        AllocationRegistry::set_global_tracker(FailureTracker)
+            .expect("no other global tracker should be set");
+
+        let token = AllocationGroupToken::register().expect("failed to register allocation group");
+        EXPECTED_GROUP_ID.store(token.id().as_usize().get(), Ordering::SeqCst);
+
+        AllocationRegistry::enable_tracking();
+        token.with(|| drop(Vec::<u8>::with_capacity(FAILING_SIZE)));
+        return;
+    }
+
+    let output = Command::new(env::current_exe().expect("failed to get test executable"))
+        .args(["--exact", "test", "--test-threads", "1"])
+        .env(CHILD_ENV_VAR, "1")
+        .output()
+        .expect("failed to run child process");
+
+    assert!(
+        !output.status.success(),
+        "failed allocation should have aborted"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let expected = format!(
+        "allocation of {} bytes failed in expected group: true",
+        FAILING_SIZE
+    );
+    assert!(stderr.contains(&expected), "unexpected stderr: {}", stderr);
+}
@@ -0,0 +1,90 @@
+//! Containers using a tracked allocator should have their allocations attributed to its allocation group, regardless of
+//! which allocation group is active on the current thread.
+#![feature(allocator_api)]
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+    TrackedAlloc,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+static ALLOCATED: Mutex<Vec<(usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+static DEALLOCATED: Mutex<Vec<(usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_LEN: usize = 3_333;
+
+struct ContainerTracker;
+
+impl AllocationTracker for ContainerTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_LEN {
+            ALLOCATED.lock().unwrap().push((object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_LEN {
+            DEALLOCATED
+                .lock()
+                .unwrap()
+                .push((object_size, source_group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(ContainerTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let container_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let active_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+
+    // The container's allocations go to its own group, while regular allocations go to the active group.
+    let (buf, regular) = active_token.with(|| {
+        let mut buf =
+            Vec::<u8, _>::with_capacity_in(MARKER_LEN, TrackedAlloc::for_group(&container_token));
+        buf.push(1);
+        (buf, Vec::<u8>::with_capacity(MARKER_LEN))
+    });
+    drop(buf);
+    drop(regular);
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *ALLOCATED.lock().unwrap(),
+        vec![
+            (MARKER_LEN, container_token.id()),
+            (MARKER_LEN, active_token.id()),
+        ]
+    );
+    assert_eq!(
+        *DEALLOCATED.lock().unwrap(),
+        vec![
+            (MARKER_LEN, container_token.id()),
+            (MARKER_LEN, active_token.id()),
+        ]
+    );
+}
@@ -0,0 +1,229 @@
+//! End-to-end attribution of real allocations, made through real collections, to the allocation group that was active
+//! when they were made.
+//!
+//! Each test registers its own allocation group, and the tracker only keeps counts keyed by allocation group, so any
+//! allocations made by the test harness itself, which are attributed to the root allocation group, don't interfere.
+
+use std::{
+    alloc::System,
+    collections::HashMap,
+    sync::{Mutex, Once},
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+static LIVE_BYTES: Mutex<Option<HashMap<AllocationGroupId, isize>>> = Mutex::new(None);
+
+struct LiveBytesTracker;
+
+impl AllocationTracker for LiveBytesTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        let mut live_bytes = LIVE_BYTES.lock().unwrap();
+        *live_bytes
+            .get_or_insert_with(HashMap::new)
+            .entry(group_id)
+            .or_default() += object_size as isize;
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        let mut live_bytes = LIVE_BYTES.lock().unwrap();
+        *live_bytes
+            .get_or_insert_with(HashMap::new)
+            .entry(source_group_id)
+            .or_default() -= object_size as isize;
+    }
+}
+
+fn install_tracker() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        AllocationRegistry::set_global_tracker(LiveBytesTracker)
+            .expect("no other global tracker should be set");
+        AllocationRegistry::enable_tracking();
+    });
+}
+
+fn live_bytes(group_id: &AllocationGroupId) -> isize {
+    LIVE_BYTES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|live_bytes| live_bytes.get(group_id).copied())
+        .unwrap_or(0)
+}
+
+#[test]
+fn vec_is_attributed_to_active_group() {
+    install_tracker();
+
+    let mut token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    let guard = token.enter();
+    let buf = Vec::<u64>::with_capacity(1024);
+    drop(guard);
+
+    let expected = (buf.capacity() * std::mem::size_of::<u64>()) as isize;
+    assert!(live_bytes(&group_id) >= expected);
+
+    // Dropping the vector outside of the group still credits the bytes back to the group that allocated them.
+    drop(buf);
+    assert_eq!(live_bytes(&group_id), 0);
+}
+
+#[test]
+fn growing_collections_stay_balanced() {
+    install_tracker();
+
+    let mut token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    let guard = token.enter();
+    let mut map = HashMap::new();
+    for i in 0..256 {
+        map.insert(i, i.to_string());
+    }
+    drop(guard);
+
+    assert!(live_bytes(&group_id) > 0);
+
+    // The map is moved to, and dropped on, another thread, which has no active allocation group.
+    std::thread::spawn(move || drop(map))
+        .join()
+        .expect("thread should not panic");
+    assert_eq!(live_bytes(&group_id), 0);
+}
+
+#[test]
+fn guard_id_matches_tracked_group() {
+    install_tracker();
+
+    let mut token = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    let guard = token.enter();
+    let group_id = guard.id();
+    let buf = Vec::<u64>::with_capacity(1024);
+    drop(guard);
+
+    assert_eq!(group_id, token.id());
+    assert!(live_bytes(&group_id) > 0);
+    drop(buf);
+}
+
+#[test]
+fn token_can_be_entered_by_reference() {
+    install_tracker();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    // The token is only borrowed, so it can be entered again on every iteration, and even while already entered.
+    let mut bufs = Vec::new();
+    for _ in 0..4 {
+        let outer = token.enter_ref();
+        let inner = token.enter_ref();
+        bufs.push(Vec::<u64>::with_capacity(256));
+        drop(inner);
+        drop(outer);
+    }
+
+    assert_eq!(token.id(), group_id);
+    assert!(live_bytes(&group_id) >= (4 * 256 * std::mem::size_of::<u64>()) as isize);
+    drop(bufs);
+    assert_eq!(live_bytes(&group_id), 0);
+}
+
+#[test]
+fn closure_runs_in_group_and_restores_previous_group() {
+    install_tracker();
+
+    let outer_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let inner_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let panicking_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let outer_id = outer_token.id();
+    let inner_id = inner_token.id();
+
+    let (inner_buf, outer_buf) = outer_token.with(|| {
+        let inner_buf = inner_token.with(|| Vec::<u64>::with_capacity(256));
+
+        // The closure unwinding still exits the group, so the outer group is active again afterwards.
+        let result = std::panic::catch_unwind(|| panicking_token.with(|| panic!("unwinding")));
+        assert!(result.is_err());
+
+        (inner_buf, Vec::<u64>::with_capacity(512))
+    });
+
+    let size = std::mem::size_of::<u64>() as isize;
+    assert_eq!(live_bytes(&inner_id), 256 * size);
+    assert_eq!(live_bytes(&outer_id), 512 * size);
+    drop(inner_buf);
+    drop(outer_buf);
+}
+
+#[test]
+fn spawned_thread_runs_in_group() {
+    install_tracker();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    let handle = tracking_allocator::spawn_in_group(token, || {
+        let buf = Vec::<u64>::with_capacity(1024);
+        (tracking_allocator::current_allocation_group(), buf)
+    });
+    let (current, buf) = handle.join().expect("thread should not panic");
+
+    assert_eq!(current, Some(group_id));
+    assert_eq!(
+        live_bytes(&group_id),
+        (buf.capacity() * std::mem::size_of::<u64>()) as isize
+    );
+    drop(buf);
+    assert_eq!(live_bytes(&group_id), 0);
+}
+
+#[test]
+fn reentering_active_group_is_a_no_op() {
+    install_tracker();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    let outer = token.enter_ref();
+    let inner = token.enter_ref();
+    assert_eq!(tracking_allocator::active_group_stack(), vec![group_id]);
+    let buf = Vec::<u64>::with_capacity(1024);
+    drop(inner);
+
+    // Dropping the nested guard leaves the group active.
+    assert_eq!(
+        tracking_allocator::current_allocation_group(),
+        Some(group_id)
+    );
+    drop(buf);
+    drop(outer);
+
+    assert!(tracking_allocator::active_group_stack().is_empty());
+    assert_eq!(live_bytes(&group_id), 0);
+}
@@ -0,0 +1,77 @@
+//! Allocation groups registered and entered through the C API should be attributed just like any other, and exiting
+//! should restore the previously active allocation group, or do nothing if no allocation group was entered.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    current_allocation_group, tracking_alloc_group_acquire, tracking_alloc_group_enter,
+    tracking_alloc_group_exit, AllocationGroupId, AllocationGroupToken, AllocationRegistry,
+    AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_SIZE: usize = 7_013;
+
+static ALLOCATED: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+
+struct CTracker;
+
+impl AllocationTracker for CTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            ALLOCATED.lock().unwrap().push(group_id);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+fn allocate_marker() {
+    drop(Vec::<u8>::with_capacity(MARKER_SIZE));
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(CTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let raw_id = tracking_alloc_group_acquire();
+    assert_ne!(raw_id, 0);
+    let id = AllocationGroupId::from_raw(raw_id as usize).unwrap();
+
+    // Exiting without having entered anything does nothing, and neither does entering the invalid group ID.
+    tracking_alloc_group_exit();
+    tracking_alloc_group_enter(0);
+    assert_eq!(current_allocation_group(), Some(AllocationGroupId::ROOT));
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    token.with(|| {
+        tracking_alloc_group_enter(raw_id);
+        allocate_marker();
+        tracking_alloc_group_exit();
+        allocate_marker();
+    });
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(*ALLOCATED.lock().unwrap(), vec![id, token.id()]);
+    assert_eq!(current_allocation_group(), Some(AllocationGroupId::ROOT));
+}
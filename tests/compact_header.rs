@@ -0,0 +1,80 @@
+//! With a compact header, allocations should only be extended by a `u32`, while still being attributed to the right
+//! allocation group when they're deallocated. With `track-lifetime` also enabled, the trailer holds an eight-byte
+//! aligned sequence number as well, so the savings are gone.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_SIZE: usize = 4_003;
+
+static EVENTS: Mutex<Vec<(&'static str, usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+struct CompactTracker;
+
+impl AllocationTracker for CompactTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("allocated", wrapped_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("deallocated", wrapped_size, source_group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(CompactTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    token.with(|| drop(Vec::<u8>::with_capacity(MARKER_SIZE)));
+
+    AllocationRegistry::disable_tracking();
+
+    // The object is byte-aligned, so the trailer only needs to be padded up to its own alignment: that of a `u32`, or
+    // that of the `u64` sequence number with `track-lifetime`, followed by the `u32` group ID padded out to eight
+    // bytes.
+    let wrapped_size = if cfg!(feature = "track-lifetime") {
+        MARKER_SIZE + 5 + 16
+    } else {
+        MARKER_SIZE + 1 + 4
+    };
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("allocated", wrapped_size, token.id()),
+            ("deallocated", wrapped_size, token.id()),
+        ]
+    );
+}
@@ -0,0 +1,47 @@
+//! The context change hook should be called with the newly active allocation group every time an allocation group is
+//! entered or exited.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{AllocationGroupId, AllocationGroupToken, AllocationRegistry, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+static CHANGES: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+
+fn record_change(current: AllocationGroupId) {
+    CHANGES.lock().unwrap().push(current);
+}
+
+#[test]
+fn test() {
+    let mut outer_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let mut inner_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+
+    AllocationRegistry::set_context_change_hook(record_change);
+
+    let outer_guard = outer_token.enter();
+    let inner_guard = inner_token.enter();
+    let suspended_guard = inner_guard.suspend();
+    drop(suspended_guard);
+    drop(outer_guard);
+
+    AllocationRegistry::clear_context_change_hook();
+
+    // Changes after the hook is cleared are not seen.
+    drop(outer_token.enter());
+
+    assert_eq!(
+        *CHANGES.lock().unwrap(),
+        vec![
+            outer_token.id(),
+            inner_token.id(),
+            outer_token.id(),
+            AllocationGroupId::ROOT,
+            outer_token.id(),
+            AllocationGroupId::ROOT,
+        ]
+    );
+}
@@ -0,0 +1,107 @@
+//! Deallocations in a different allocation group than the one that made the allocation, including reallocations,
+//! should be reported as cross-group deallocations, in addition to the regular callbacks.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const SAME_GROUP_SIZE: usize = 1_111;
+const CROSS_GROUP_SIZE: usize = 2_222;
+const GROWN_SIZE: usize = 4_444;
+const SIZES: [usize; 3] = [SAME_GROUP_SIZE, CROSS_GROUP_SIZE, GROWN_SIZE];
+
+static DEALLOCATED: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+static CROSS_GROUP: Mutex<Vec<(usize, AllocationGroupId, AllocationGroupId)>> =
+    Mutex::new(Vec::new());
+
+struct CrossGroupTracker;
+
+impl AllocationTracker for CrossGroupTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            DEALLOCATED.lock().unwrap().push(object_size);
+        }
+    }
+
+    fn deallocated_cross_group(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            CROSS_GROUP
+                .lock()
+                .unwrap()
+                .push((object_size, source_group_id, current_group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(CrossGroupTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let allocating = AllocationGroupToken::register().expect("failed to register allocation group");
+    let freeing = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    let (same_group, cross_group, mut growing) = allocating.with(|| {
+        (
+            Vec::<u8>::with_capacity(SAME_GROUP_SIZE),
+            Vec::<u8>::with_capacity(CROSS_GROUP_SIZE),
+            Vec::<u8>::with_capacity(CROSS_GROUP_SIZE),
+        )
+    });
+
+    allocating.with(|| drop(same_group));
+    freeing.with(|| {
+        drop(cross_group);
+        growing.reserve_exact(GROWN_SIZE);
+    });
+    freeing.with(|| drop(growing));
+
+    AllocationRegistry::disable_tracking();
+
+    // Every deallocation is still passed to the regular callbacks, including the original block of the reallocation.
+    assert_eq!(
+        *DEALLOCATED.lock().unwrap(),
+        vec![
+            SAME_GROUP_SIZE,
+            CROSS_GROUP_SIZE,
+            CROSS_GROUP_SIZE,
+            GROWN_SIZE
+        ]
+    );
+    assert_eq!(
+        *CROSS_GROUP.lock().unwrap(),
+        vec![
+            (CROSS_GROUP_SIZE, allocating.id(), freeing.id()),
+            (CROSS_GROUP_SIZE, allocating.id(), freeing.id()),
+        ]
+    );
+}
@@ -0,0 +1,76 @@
+//! The allocation group that allocations are currently attributed to should be observable from anywhere on the thread,
+//! following groups as they're entered and exited, except from within the tracker, where nothing is attributed. The
+//! full stack of entered groups should be observable as well.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    active_group_stack, current_allocation_group, AllocationGroupId, AllocationGroupToken,
+    AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_SIZE: usize = 4_321;
+
+static SEEN_BY_TRACKER: Mutex<Vec<Option<AllocationGroupId>>> = Mutex::new(Vec::new());
+
+struct CurrentGroupTracker;
+
+impl AllocationTracker for CurrentGroupTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            SEEN_BY_TRACKER
+                .lock()
+                .unwrap()
+                .push(current_allocation_group());
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(CurrentGroupTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    assert_eq!(current_allocation_group(), Some(AllocationGroupId::ROOT));
+
+    let outer = AllocationGroupToken::register().expect("failed to register allocation group");
+    let inner = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    outer.with(|| {
+        assert_eq!(current_allocation_group(), Some(outer.id()));
+        inner.with(|| {
+            assert_eq!(current_allocation_group(), Some(inner.id()));
+            assert_eq!(active_group_stack(), vec![outer.id(), inner.id()]);
+            drop(Vec::<u8>::with_capacity(MARKER_SIZE));
+        });
+        assert_eq!(current_allocation_group(), Some(outer.id()));
+    });
+
+    assert_eq!(current_allocation_group(), Some(AllocationGroupId::ROOT));
+    assert!(active_group_stack().is_empty());
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(*SEEN_BY_TRACKER.lock().unwrap(), vec![None]);
+}
@@ -0,0 +1,129 @@
+//! Wrapping a custom allocator should pass reallocations through to it, so that allocators which can grow allocations
+//! in place, such as jemalloc with its size classes, still get to do so, and the group ID should survive being moved
+//! along with the end of the object.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+/// An allocator that rounds every allocation up to a power-of-two size class, and reallocates in place whenever the new
+/// size fits in the same size class.
+struct SizeClassAllocator;
+
+fn size_class(size: usize, align: usize) -> Layout {
+    Layout::from_size_align(size.next_power_of_two(), align).unwrap()
+}
+
+unsafe impl GlobalAlloc for SizeClassAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(size_class(layout.size(), layout.align()))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, size_class(layout.size(), layout.align()));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let old_class = size_class(layout.size(), layout.align());
+        let new_class = size_class(new_size, layout.align());
+        if old_class == new_class {
+            if GROWN_WRAPPED_SIZES.contains(&new_size) {
+                IN_PLACE_REALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+            }
+            ptr
+        } else {
+            System.realloc(ptr, old_class, new_class.size())
+        }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Allocator<SizeClassAllocator> = Allocator::from_allocator(SizeClassAllocator);
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself. Both sizes, plus the group ID, fit in the same size class.
+const INITIAL_SIZE: usize = 3_000;
+const GROWN_SIZE: usize = 3_500;
+const SIZES: [usize; 2] = [INITIAL_SIZE, GROWN_SIZE];
+
+// The wrapped size of the grown allocation, which depends on the size of the trailer and any padding before it.
+const GROWN_WRAPPED_SIZES: std::ops::RangeInclusive<usize> = GROWN_SIZE + 1..=GROWN_SIZE + 32;
+
+static IN_PLACE_REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static EVENTS: Mutex<Vec<(&'static str, usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+struct SizeClassTracker;
+
+impl AllocationTracker for SizeClassTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("allocated", object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("deallocated", object_size, source_group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(SizeClassTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let buf = token.with(|| {
+        let mut buf = Vec::<u8>::with_capacity(INITIAL_SIZE);
+        buf.extend((0..INITIAL_SIZE).map(|i| i as u8));
+        let ptr = buf.as_ptr();
+        buf.reserve_exact(GROWN_SIZE - INITIAL_SIZE);
+        assert_eq!(buf.as_ptr(), ptr, "allocation should have grown in place");
+        buf
+    });
+
+    // Deallocating outside of the allocation group still finds the group ID at its new offset.
+    assert!(buf.iter().copied().eq((0..INITIAL_SIZE).map(|i| i as u8)));
+    drop(buf);
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(IN_PLACE_REALLOCATIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("allocated", INITIAL_SIZE, token.id()),
+            ("deallocated", INITIAL_SIZE, token.id()),
+            ("allocated", GROWN_SIZE, token.id()),
+            ("deallocated", GROWN_SIZE, token.id()),
+        ]
+    );
+}
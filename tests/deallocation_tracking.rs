@@ -0,0 +1,82 @@
+//! With deallocation tracking disabled, allocations and reallocations should still be tracked, while deallocations are
+//! never passed to the tracker, not even as untracked deallocations.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system().with_deallocation_tracking(false);
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const INITIAL_SIZE: usize = 2_003;
+const GROWN_SIZE: usize = 8_003;
+const SIZES: [usize; 2] = [INITIAL_SIZE, GROWN_SIZE];
+
+static EVENTS: Mutex<Vec<(&'static str, usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+struct AllocationRateTracker;
+
+impl AllocationTracker for AllocationRateTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("allocated", object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("deallocated", object_size, source_group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    assert!(!ALLOCATOR.config().deallocation_tracking_enabled);
+
+    AllocationRegistry::set_global_tracker(AllocationRateTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_untracked_deallocations();
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    token.with(|| {
+        let mut buf = Vec::<u8>::with_capacity(INITIAL_SIZE);
+        buf.extend((0..INITIAL_SIZE).map(|i| i as u8));
+        buf.reserve_exact(GROWN_SIZE - INITIAL_SIZE);
+        assert!(buf.iter().copied().eq((0..INITIAL_SIZE).map(|i| i as u8)));
+    });
+
+    AllocationRegistry::disable_tracking();
+    AllocationRegistry::disable_untracked_deallocations();
+
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("allocated", INITIAL_SIZE, token.id()),
+            ("allocated", GROWN_SIZE, token.id()),
+        ]
+    );
+}
@@ -0,0 +1,100 @@
+//! When a value is dropped in a different allocation group than the one it was allocated in, its deallocations should
+//! carry the allocating group as the source and the dropping group as the current group, while any allocations made by
+//! its destructor are attributed to the dropping group.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const BUFFER_SIZE: usize = 20_000;
+const DROP_SIZE: usize = 20_001;
+
+static ALLOCATED: Mutex<Vec<(usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+static DEALLOCATED: Mutex<Vec<(usize, AllocationGroupId, AllocationGroupId)>> =
+    Mutex::new(Vec::new());
+
+struct MarkerTracker;
+
+impl AllocationTracker for MarkerTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == BUFFER_SIZE || object_size == DROP_SIZE {
+            ALLOCATED.lock().unwrap().push((object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        if object_size == BUFFER_SIZE || object_size == DROP_SIZE {
+            DEALLOCATED
+                .lock()
+                .unwrap()
+                .push((object_size, source_group_id, current_group_id));
+        }
+    }
+}
+
+struct AllocatesOnDrop {
+    _buf: Vec<u8>,
+}
+
+impl Drop for AllocatesOnDrop {
+    fn drop(&mut self) {
+        drop(Vec::<u8>::with_capacity(DROP_SIZE));
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(MarkerTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut allocating_token =
+        AllocationGroupToken::register().expect("failed to register allocating group");
+    let mut dropping_token =
+        AllocationGroupToken::register().expect("failed to register dropping group");
+    let allocating_id = allocating_token.id();
+    let dropping_id = dropping_token.id();
+
+    let guard = allocating_token.enter();
+    let value = AllocatesOnDrop {
+        _buf: Vec::with_capacity(BUFFER_SIZE),
+    };
+    drop(guard);
+
+    let guard = dropping_token.enter();
+    drop(value);
+    drop(guard);
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *ALLOCATED.lock().unwrap(),
+        vec![(BUFFER_SIZE, allocating_id), (DROP_SIZE, dropping_id),]
+    );
+    assert_eq!(
+        *DEALLOCATED.lock().unwrap(),
+        vec![
+            (DROP_SIZE, dropping_id, dropping_id),
+            (BUFFER_SIZE, allocating_id, dropping_id),
+        ]
+    );
+}
@@ -0,0 +1,167 @@
+//! With the `event-sequence` feature, every tracked event should get a unique sequence number from a single counter, so
+//! that events from different threads can be merged into an order in which every address is always freed before it's
+//! handed out again.
+
+use std::{
+    alloc::System,
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+use tracking_allocator::{
+    spawn_in_group, AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker,
+    Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only events of these sizes are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const INITIAL_SIZE: usize = 2_003;
+const GROWN_SIZE: usize = 8_003;
+const SIZES: [usize; 2] = [INITIAL_SIZE, GROWN_SIZE];
+
+const THREADS: usize = 4;
+const ITERATIONS: usize = 200;
+
+#[derive(Clone, Copy, Debug)]
+enum Event {
+    Allocated(usize),
+    Deallocated(usize),
+    Reallocated(usize, usize),
+}
+
+static EVENTS: Mutex<Vec<(u64, Event)>> = Mutex::new(Vec::new());
+
+struct SequenceTracker;
+
+impl AllocationTracker for SequenceTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn allocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        _group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push((sequence, Event::Allocated(addr)));
+        }
+    }
+
+    fn deallocated_with_sequence(
+        &self,
+        addr: usize,
+        object_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push((sequence, Event::Deallocated(addr)));
+        }
+    }
+
+    fn reallocated_with_sequence(
+        &self,
+        old_addr: usize,
+        new_addr: usize,
+        old_object_size: usize,
+        _new_object_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+        sequence: u64,
+    ) {
+        if SIZES.contains(&old_object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push((sequence, Event::Reallocated(old_addr, new_addr)));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(SequenceTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    // Allocating and freeing the same size on several threads makes it likely that addresses are reused across threads.
+    let handles = (0..THREADS)
+        .map(|_| {
+            let token =
+                AllocationGroupToken::register().expect("failed to register allocation group");
+            spawn_in_group(token, || {
+                for _ in 0..ITERATIONS {
+                    drop(Vec::<u8>::with_capacity(INITIAL_SIZE));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().expect("thread should not panic");
+    }
+
+    token.with(|| {
+        let mut buf = Vec::<u8>::with_capacity(INITIAL_SIZE);
+        buf.reserve_exact(GROWN_SIZE);
+    });
+
+    AllocationRegistry::disable_tracking();
+
+    let mut events = EVENTS.lock().unwrap().clone();
+    assert_eq!(events.len(), THREADS * ITERATIONS * 2 + 3);
+
+    let sequences = events
+        .iter()
+        .map(|(sequence, _)| *sequence)
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        sequences.len(),
+        events.len(),
+        "sequence numbers should be unique"
+    );
+
+    // Replaying the events in sequence order should never see an address handed out while it's still live, or freed
+    // when it isn't.
+    events.sort_by_key(|(sequence, _)| *sequence);
+    let mut live = HashMap::new();
+    for (sequence, event) in events {
+        match event {
+            Event::Allocated(addr) => assert!(live.insert(addr, sequence).is_none(), "{:?}", event),
+            Event::Deallocated(addr) => assert!(live.remove(&addr).is_some(), "{:?}", event),
+            Event::Reallocated(old_addr, new_addr) => {
+                assert!(live.remove(&old_addr).is_some(), "{:?}", event);
+                assert!(live.insert(new_addr, sequence).is_none(), "{:?}", event);
+            }
+        }
+    }
+    assert!(live.is_empty());
+}
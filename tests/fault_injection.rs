@@ -0,0 +1,74 @@
+//! A tracker should be able to deterministically fail allocations, which downstream code then observes as the
+//! allocator having run out of memory.
+
+use std::{
+    alloc::System,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Allocations of exactly this size are failed, so that we never fail allocations made by the test harness itself.
+const FAILING_SIZE: usize = 12_345;
+
+static FAILING_SIZE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct FailingTracker;
+
+impl AllocationTracker for FailingTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_size == FAILING_SIZE {
+            FAILING_SIZE_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn should_fail_allocation(&self, object_size: usize, group_id: AllocationGroupId) -> bool {
+        object_size == FAILING_SIZE && group_id != AllocationGroupId::ROOT
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(FailingTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut local_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+
+    let mut buf = Vec::<u8>::new();
+    let guard = local_token.enter();
+    assert!(buf.try_reserve_exact(FAILING_SIZE).is_err());
+    assert!(buf.try_reserve_exact(FAILING_SIZE + 1).is_ok());
+    drop(guard);
+
+    // The failed allocation never reaches the tracker.
+    assert_eq!(FAILING_SIZE_ALLOCATIONS.load(Ordering::SeqCst), 0);
+
+    // Outside of the allocation group, the same allocation succeeds.
+    let mut buf = Vec::<u8>::new();
+    assert!(buf.try_reserve_exact(FAILING_SIZE).is_ok());
+    assert_eq!(FAILING_SIZE_ALLOCATIONS.load(Ordering::SeqCst), 1);
+
+    AllocationRegistry::disable_tracking();
+}
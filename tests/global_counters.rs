@@ -0,0 +1,56 @@
+//! Global allocation counters should be updated for every allocation, deallocation, and reallocation, even when
+//! tracking is disabled and no tracker has been installed. Allocations made while no allocation group is active should
+//! be counted separately while tracking is enabled.
+
+use std::alloc::{alloc, alloc_zeroed, dealloc, realloc, Layout, System};
+use tracking_allocator::{
+    global_allocation_counters, AllocationRegistry, Allocator, GlobalCounters, NoopTracker,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+#[test]
+fn test() {
+    let layout = Layout::from_size_align(1_000, 8).unwrap();
+
+    // Counters are process-wide, so the test harness may allocate concurrently: we can only check lower bounds.
+    let before = global_allocation_counters();
+    unsafe {
+        let ptr = alloc(layout);
+        assert!(!ptr.is_null());
+        let ptr = realloc(ptr, layout, 3_000);
+        assert!(!ptr.is_null());
+        dealloc(ptr, Layout::from_size_align(3_000, 8).unwrap());
+
+        let zeroed = alloc_zeroed(layout);
+        assert!(!zeroed.is_null());
+        dealloc(zeroed, layout);
+    }
+    let after = global_allocation_counters();
+
+    let GlobalCounters {
+        total_allocations,
+        total_deallocations,
+        total_bytes_allocated,
+        ..
+    } = after;
+    assert!(total_allocations - before.total_allocations >= 3);
+    assert!(total_deallocations - before.total_deallocations >= 3);
+    assert!(total_bytes_allocated - before.total_bytes_allocated >= 5_000);
+}
+
+#[test]
+fn root_allocations() {
+    AllocationRegistry::set_global_tracker(NoopTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let before = global_allocation_counters();
+    drop(Box::new([0u8; 64]));
+    let after = global_allocation_counters();
+
+    AllocationRegistry::disable_tracking();
+
+    assert!(after.root_allocations > before.root_allocations);
+}
@@ -0,0 +1,54 @@
+//! The registered group count should include every allocation group registered, however it was registered, including
+//! concurrently, and should not change when registration fails.
+
+use std::{alloc::System, thread};
+use tracking_allocator::{AllocationGroupToken, AllocationRegistry, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+const THREADS: usize = 4;
+const PER_THREAD: usize = 50;
+
+#[test]
+fn test() {
+    let initial = AllocationRegistry::registered_group_count();
+
+    let parent = AllocationGroupToken::register().expect("failed to register allocation group");
+    let _child = AllocationGroupToken::register_with_parent(&parent)
+        .expect("failed to register child allocation group");
+    let _named = AllocationGroupToken::register_named("group_count")
+        .expect("failed to register named allocation group");
+    let _many =
+        AllocationGroupToken::register_many(5).expect("failed to register allocation groups");
+    assert_eq!(AllocationRegistry::registered_group_count(), initial + 8);
+
+    // Looking up an existing named allocation group doesn't register it again.
+    let _named = AllocationGroupToken::register_named("group_count")
+        .expect("failed to get named allocation group");
+    assert_eq!(AllocationRegistry::registered_group_count(), initial + 8);
+
+    let handles = (0..THREADS)
+        .map(|_| {
+            thread::spawn(|| {
+                for _ in 0..PER_THREAD {
+                    AllocationGroupToken::register().expect("failed to register allocation group");
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().expect("thread should not panic");
+    }
+    assert_eq!(
+        AllocationRegistry::registered_group_count(),
+        initial + 8 + THREADS * PER_THREAD
+    );
+
+    // Failing to register leaves the count untouched.
+    assert!(AllocationGroupToken::register_many(usize::MAX).is_none());
+    assert_eq!(
+        AllocationRegistry::registered_group_count(),
+        initial + 8 + THREADS * PER_THREAD
+    );
+}
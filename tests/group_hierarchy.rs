@@ -0,0 +1,88 @@
+//! Allocation groups can be registered as children of other allocation groups, and the resulting hierarchy can be
+//! walked from any allocation group up to its root, while allocations are still only attributed to the active group.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_SIZE: usize = 6_007;
+
+static ALLOCATED: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+static REGISTERED: Mutex<Vec<(AllocationGroupId, Option<AllocationGroupId>)>> =
+    Mutex::new(Vec::new());
+
+struct HierarchyTracker;
+
+impl AllocationTracker for HierarchyTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            ALLOCATED.lock().unwrap().push(group_id);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn group_registered(&self, group_id: AllocationGroupId) {
+        let parent = AllocationRegistry::group_parent(&group_id);
+        REGISTERED.lock().unwrap().push((group_id, parent));
+    }
+}
+
+fn ancestors(id: &AllocationGroupId) -> Vec<AllocationGroupId> {
+    std::iter::successors(AllocationRegistry::group_parent(id), |parent| {
+        AllocationRegistry::group_parent(parent)
+    })
+    .collect()
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(HierarchyTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let server = AllocationGroupToken::register().expect("failed to register server group");
+    let connection = AllocationGroupToken::register_with_parent(&server)
+        .expect("failed to register connection group");
+    let request = AllocationGroupToken::register_with_parent(&connection)
+        .expect("failed to register request group");
+
+    // Only the active group is attributed, not its ancestors.
+    request.with(|| drop(Vec::<u8>::with_capacity(MARKER_SIZE)));
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(AllocationRegistry::group_parent(&server.id()), None);
+    assert_eq!(ancestors(&request.id()), vec![connection.id(), server.id()]);
+    assert_eq!(*ALLOCATED.lock().unwrap(), vec![request.id()]);
+
+    // The tracker can already see the parent when it's told about the registration.
+    assert_eq!(
+        *REGISTERED.lock().unwrap(),
+        vec![
+            (server.id(), None),
+            (connection.id(), Some(server.id())),
+            (request.id(), Some(connection.id())),
+        ]
+    );
+}
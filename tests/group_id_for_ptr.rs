@@ -0,0 +1,36 @@
+//! The allocation group of a live allocation can be looked up without deallocating it, and allocations that were never
+//! attributed to an allocation group have none.
+
+use std::alloc::{alloc, dealloc, Layout, System};
+use tracking_allocator::{AllocationGroupToken, AllocationRegistry, Allocator, NoopTracker};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(NoopTracker)
+        .expect("no other global tracker should be set");
+    let layout = Layout::from_size_align(24, 8).unwrap();
+
+    // Tracking is disabled, so this allocation isn't attributed to anything.
+    let untracked = unsafe { alloc(layout) };
+    assert!(!untracked.is_null());
+
+    AllocationRegistry::enable_tracking();
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let tracked = token.with(|| unsafe { alloc(layout) });
+    assert!(!tracked.is_null());
+    AllocationRegistry::disable_tracking();
+
+    unsafe {
+        assert_eq!(ALLOCATOR.group_id_for_ptr(untracked, layout), None);
+        assert_eq!(
+            ALLOCATOR.group_id_for_ptr(tracked, layout),
+            Some(token.id())
+        );
+
+        dealloc(untracked, layout);
+        dealloc(tracked, layout);
+    }
+}
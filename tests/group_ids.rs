@@ -0,0 +1,57 @@
+//! Group IDs can be converted to and from raw integers, such as when persisting them, and reloaded IDs refer to the
+//! same allocation group as the original. Groups registered together get contiguous IDs, and a batch that can never fit
+//! doesn't stop later registrations.
+
+use std::convert::TryFrom;
+use tracking_allocator::{AllocationGroupId, AllocationGroupToken, AllocationRegistry};
+
+#[test]
+fn raw_round_trip() {
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let id = token.id();
+    AllocationRegistry::set_group_name(&id, "persisted");
+
+    let raw = id.as_usize().get();
+    let reloaded = AllocationGroupId::from_raw(raw).expect("raw group ID should not be zero");
+    assert_eq!(reloaded, id);
+    assert_eq!(AllocationGroupId::try_from(raw).ok(), Some(id));
+    assert_eq!(AllocationRegistry::group_name(&reloaded), Some("persisted"));
+}
+
+#[test]
+fn zero_is_rejected() {
+    assert_eq!(AllocationGroupId::from_raw(0), None);
+    assert!(AllocationGroupId::try_from(0).is_err());
+}
+
+#[test]
+fn register_many_is_contiguous() {
+    let tokens =
+        AllocationGroupToken::register_many(4).expect("failed to register allocation groups");
+    let raw_ids = tokens
+        .iter()
+        .map(|token| token.id().as_usize().get())
+        .collect::<Vec<_>>();
+    let first = raw_ids[0];
+    assert_eq!(raw_ids, (first..first + 4).collect::<Vec<_>>());
+
+    assert_eq!(
+        AllocationGroupToken::register_many(0).map(|tokens| tokens.len()),
+        Some(0)
+    );
+}
+
+#[test]
+fn oversized_batch_leaves_ids_for_later() {
+    let before = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    // A batch that can never fit fails on its own, without using up the IDs that are left.
+    assert!(AllocationGroupToken::register_many(usize::MAX / 2).is_none());
+    assert!(AllocationGroupToken::register_many(usize::MAX).is_none());
+
+    let after = AllocationGroupToken::register().expect("failed to register allocation group");
+    assert!(after.id() > before.id());
+    let batch =
+        AllocationGroupToken::register_many(2).expect("failed to register allocation groups");
+    assert_eq!(batch.len(), 2);
+}
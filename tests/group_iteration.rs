@@ -0,0 +1,52 @@
+//! Iterating over allocation groups should visit every registered allocation group exactly once, in order, along with
+//! its name.
+
+use std::alloc::System;
+use tracking_allocator::{AllocationGroupId, AllocationGroupToken, AllocationRegistry, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+fn visit_all() -> Vec<(AllocationGroupId, Option<&'static str>)> {
+    let mut groups = Vec::new();
+    AllocationRegistry::for_each_group(|id, name| groups.push((id, name)));
+    groups
+}
+
+#[test]
+fn test() {
+    let first = AllocationGroupToken::register().expect("failed to register allocation group");
+    let named = AllocationGroupToken::register_named("group_iteration")
+        .expect("failed to register named allocation group");
+    let many =
+        AllocationGroupToken::register_many(3).expect("failed to register allocation groups");
+    AllocationRegistry::set_group_name(&many[1].id(), "renamed");
+
+    let groups = visit_all();
+    assert_eq!(groups.len(), AllocationRegistry::registered_group_count());
+    assert!(groups.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(groups.iter().all(|(id, _)| *id != AllocationGroupId::ROOT));
+
+    let expected = [
+        (first.id(), None),
+        (named.id(), Some("group_iteration")),
+        (many[0].id(), None),
+        (many[1].id(), Some("renamed")),
+        (many[2].id(), None),
+    ];
+    for group in &expected {
+        assert!(groups.contains(group), "missing {:?}", group);
+    }
+
+    // Groups registered from within the callback aren't visited, and don't upset the iteration.
+    let before = groups.len();
+    let mut visited = 0;
+    let mut registered = Vec::new();
+    AllocationRegistry::for_each_group(|_, _| {
+        visited += 1;
+        registered
+            .push(AllocationGroupToken::register().expect("failed to register allocation group"));
+    });
+    assert_eq!(visited, before);
+    assert_eq!(visit_all().len(), before * 2);
+}
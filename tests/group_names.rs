@@ -0,0 +1,55 @@
+//! Allocation groups can be given human-readable names, and fall back to a label derived from their group ID. Groups
+//! registered by name are shared by everyone who registers that name.
+
+use tracking_allocator::{AllocationGroupToken, AllocationRegistry};
+
+#[test]
+fn unnamed_group_uses_id_label() {
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let id = token.id();
+
+    assert_eq!(AllocationRegistry::group_name(&id), None);
+    assert_eq!(
+        AllocationRegistry::group_label(&id),
+        format!("group#{}", id.as_usize())
+    );
+}
+
+#[test]
+fn named_group_uses_name() {
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let id = token.id();
+
+    AllocationRegistry::set_group_name(&id, "http-server");
+    assert_eq!(AllocationRegistry::group_name(&id), Some("http-server"));
+    assert_eq!(AllocationRegistry::group_label(&id), "http-server");
+
+    AllocationRegistry::set_group_name(&id, "http-client");
+    assert_eq!(AllocationRegistry::group_name(&id), Some("http-client"));
+}
+
+#[test]
+fn display_matches_label() {
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let id = token.id();
+    assert_eq!(id.to_string(), format!("group#{}", id.as_usize()));
+
+    AllocationRegistry::set_group_name(&id, "database");
+    assert_eq!(id.to_string(), "database");
+    assert_eq!(id.to_string(), AllocationRegistry::group_label(&id));
+}
+
+#[test]
+fn register_named_returns_same_group() {
+    let first =
+        AllocationGroupToken::register_named("db").expect("failed to register allocation group");
+    let second =
+        AllocationGroupToken::register_named("db").expect("failed to register allocation group");
+    let other =
+        AllocationGroupToken::register_named("cache").expect("failed to register allocation group");
+
+    assert_eq!(first.id(), second.id());
+    assert_ne!(first.id(), other.id());
+    assert_eq!(AllocationRegistry::group_name(&first.id()), Some("db"));
+    assert_eq!(AllocationRegistry::group_name(&other.id()), Some("cache"));
+}
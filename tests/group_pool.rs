@@ -0,0 +1,50 @@
+//! A group pool should hand out each of its allocation groups at most once at a time, take them back when the pooled
+//! tokens are dropped, and clear any name set while they were checked out.
+
+use std::{alloc::System, collections::HashSet};
+use tracking_allocator::{AllocationGroupPool, AllocationRegistry, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+#[test]
+fn test() {
+    let pool = AllocationGroupPool::with_capacity(4).expect("failed to register allocation groups");
+    assert_eq!(pool.capacity(), 4);
+    assert_eq!(pool.available(), 4);
+
+    let checked_out = (0..4)
+        .map(|_| pool.checkout().expect("pool should have tokens available"))
+        .collect::<Vec<_>>();
+    assert_eq!(pool.available(), 0);
+    assert!(pool.checkout().is_none());
+
+    let ids = checked_out
+        .iter()
+        .map(|token| token.id())
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        ids.len(),
+        4,
+        "each checkout should get a distinct allocation group"
+    );
+
+    for token in &checked_out {
+        AllocationRegistry::set_group_name(&token.id(), "request");
+    }
+    drop(checked_out);
+    assert_eq!(pool.available(), 4);
+
+    // Tokens are reused rather than registered afresh, and come back without the name from their last checkout.
+    let mut token = pool.checkout().expect("pool should have tokens available");
+    assert!(ids.contains(&token.id()));
+    assert_eq!(AllocationRegistry::group_name(&token.id()), None);
+
+    let guard = token.enter();
+    drop(guard);
+
+    // Clones of the pool share the same tokens.
+    assert_eq!(pool.clone().available(), 3);
+    drop(token);
+    assert_eq!(pool.available(), 4);
+}
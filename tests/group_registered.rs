@@ -0,0 +1,60 @@
+//! Trackers should be told about every allocation group that is registered while tracking, even ones that never
+//! allocate anything.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+static REGISTERED: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+
+struct RegistrationTracker;
+
+impl AllocationTracker for RegistrationTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn group_registered(&self, group_id: AllocationGroupId) {
+        REGISTERED.lock().unwrap().push(group_id);
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(RegistrationTracker)
+        .expect("no other global tracker should be set");
+
+    // Groups registered while tracking is disabled aren't reported.
+    let _before = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    AllocationRegistry::enable_tracking();
+    let first = AllocationGroupToken::register().expect("failed to register allocation group");
+    let second = AllocationGroupToken::register().expect("failed to register allocation group");
+    let many =
+        AllocationGroupToken::register_many(2).expect("failed to register allocation groups");
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *REGISTERED.lock().unwrap(),
+        vec![first.id(), second.id(), many[0].id(), many[1].id()]
+    );
+}
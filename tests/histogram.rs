@@ -0,0 +1,54 @@
+//! The histogram tracker should bucket allocation sizes by power of two, per allocation group.
+
+use std::alloc::System;
+use tracking_allocator::{
+    AllocationGroupToken, AllocationRegistry, Allocator, GroupHistogram, HistogramTracker,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+#[test]
+fn buckets() {
+    assert_eq!(GroupHistogram::bucket_for_size(0), 0);
+    assert_eq!(GroupHistogram::bucket_for_size(1), 0);
+    assert_eq!(GroupHistogram::bucket_for_size(2), 1);
+    assert_eq!(GroupHistogram::bucket_for_size(3), 2);
+    assert_eq!(GroupHistogram::bucket_for_size(4), 2);
+    assert_eq!(GroupHistogram::bucket_for_size(1025), 11);
+    assert_eq!(
+        GroupHistogram::bucket_for_size(1 << (usize::BITS - 1)),
+        usize::BITS as usize - 1
+    );
+    assert_eq!(
+        GroupHistogram::bucket_for_size(usize::MAX),
+        usize::BITS as usize
+    );
+}
+
+#[test]
+fn tracker() {
+    let histograms = HistogramTracker::new();
+    AllocationRegistry::set_global_tracker(histograms.clone())
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    token.with(|| {
+        drop(Vec::<u8>::with_capacity(100));
+        drop(Vec::<u8>::with_capacity(128));
+        drop(Vec::<u8>::with_capacity(129));
+    });
+
+    AllocationRegistry::disable_tracking();
+
+    let histogram = histograms
+        .snapshot()
+        .into_iter()
+        .find(|histogram| histogram.group_id == token.id())
+        .expect("allocation group should have a histogram");
+    assert_eq!(histogram.buckets.len(), usize::BITS as usize + 1);
+    assert_eq!(histogram.buckets[7], 2);
+    assert_eq!(histogram.buckets[8], 1);
+    assert_eq!(histogram.allocations(), 3);
+}
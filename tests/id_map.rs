@@ -0,0 +1,67 @@
+//! `IdVecMap` should behave like any other map keyed by group ID, including for the special group IDs at the top of the
+//! ID space, and iterate in ascending order of group ID.
+
+use std::collections::{BTreeMap, HashSet};
+use tracking_allocator::{AllocationGroupId, AllocationGroupToken, IdVecMap};
+
+#[test]
+fn test() {
+    let tokens =
+        AllocationGroupToken::register_many(3).expect("failed to register allocation groups");
+    let ids = tokens
+        .iter()
+        .map(AllocationGroupToken::id)
+        .collect::<Vec<_>>();
+
+    // Group IDs are `Copy`, and usable as keys in both hashed and ordered maps.
+    let unique = ids.iter().copied().collect::<HashSet<_>>();
+    assert_eq!(unique.len(), ids.len());
+    let ordered = ids.iter().map(|id| (*id, ())).collect::<BTreeMap<_, _>>();
+    assert!(ordered.keys().copied().eq(ids.iter().copied()));
+
+    let mut map = IdVecMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.get(ids[0]), None);
+    assert_eq!(map.get_mut(ids[0]), None);
+    assert_eq!(map.remove(ids[0]), None);
+
+    assert_eq!(map.insert(ids[2], "c"), None);
+    assert_eq!(map.insert(AllocationGroupId::UNTRACKED, "untracked"), None);
+    assert_eq!(map.insert(AllocationGroupId::OBSERVER, "observer"), None);
+    assert_eq!(map.insert(ids[0], "a"), None);
+    assert_eq!(map.insert(ids[0], "a2"), Some("a"));
+    assert_eq!(map.len(), 4);
+
+    assert_eq!(map.get(ids[0]), Some(&"a2"));
+    assert_eq!(map.get(ids[1]), None);
+    assert_eq!(map.get(AllocationGroupId::UNTRACKED), Some(&"untracked"));
+    assert_eq!(*map.get_or_insert_with(ids[1], || "b"), "b");
+    assert_eq!(*map.get_or_insert_with(ids[1], || "unused"), "b");
+    *map.get_mut(AllocationGroupId::OBSERVER)
+        .expect("observer entry") = "observer2";
+    assert_eq!(map.len(), 5);
+
+    let entries = map
+        .iter()
+        .map(|(id, value)| (id, *value))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        vec![
+            (ids[0], "a2"),
+            (ids[1], "b"),
+            (ids[2], "c"),
+            (AllocationGroupId::OBSERVER, "observer2"),
+            (AllocationGroupId::UNTRACKED, "untracked"),
+        ]
+    );
+
+    assert_eq!(map.remove(ids[1]), Some("b"));
+    assert_eq!(map.remove(AllocationGroupId::UNTRACKED), Some("untracked"));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get(ids[1]), None);
+
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.iter().count(), 0);
+}
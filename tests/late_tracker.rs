@@ -0,0 +1,81 @@
+//! Allocations made before the tracker is set should be left untracked, even if tracking was enabled and an allocation
+//! group was active at the time, so that the tracker is never told about deallocations it never saw allocated.
+
+use std::{
+    alloc::{alloc, dealloc, Layout, System},
+    sync::Mutex,
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_SIZE: usize = 9_011;
+
+static EVENTS: Mutex<Vec<(&'static str, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+struct LateTracker;
+
+impl AllocationTracker for LateTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            EVENTS.lock().unwrap().push(("allocated", group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("deallocated", source_group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let layout = Layout::from_size_align(MARKER_SIZE, 1).unwrap();
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    // Tracking is enabled and the allocation group is active, but there's no tracker yet.
+    AllocationRegistry::enable_tracking();
+    let early = token.with(|| unsafe { alloc(layout) });
+    assert!(!early.is_null());
+    assert_eq!(unsafe { ALLOCATOR.group_id_for_ptr(early, layout) }, None);
+
+    AllocationRegistry::set_global_tracker(LateTracker)
+        .expect("no other global tracker should be set");
+    let late = token.with(|| unsafe { alloc(layout) });
+    assert!(!late.is_null());
+
+    unsafe {
+        dealloc(early, layout);
+        dealloc(late, layout);
+    }
+
+    AllocationRegistry::disable_tracking();
+
+    // Only the allocation made after the tracker was set is seen, and only its deallocation.
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![("allocated", token.id()), ("deallocated", token.id())]
+    );
+}
@@ -0,0 +1,136 @@
+//! Trackers that implement the layout-aware callbacks should see the alignment of every allocation, including
+//! reallocations, which keep the alignment of the original allocation, and highly-aligned allocations should only pay
+//! for the group ID when the object leaves no room for it.
+
+use std::{
+    alloc::{Layout, System},
+    sync::Mutex,
+};
+use tracking_allocator::{
+    tracking_overhead, AllocationGroupId, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations with this alignment are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const ALIGN: usize = 256;
+
+#[derive(Clone, Copy)]
+#[repr(align(256))]
+struct Aligned(u8);
+
+static EVENTS: Mutex<Vec<(&'static str, usize, usize)>> = Mutex::new(Vec::new());
+static WRAPPED_SIZES: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+struct LayoutTracker;
+
+impl AllocationTracker for LayoutTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        panic!("allocated_with_layout should be called instead");
+    }
+
+    fn allocated_with_layout(
+        &self,
+        _addr: usize,
+        object_layout: Layout,
+        wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_layout.align() == ALIGN {
+            WRAPPED_SIZES
+                .lock()
+                .unwrap()
+                .push((object_layout.size(), wrapped_size));
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("allocated", object_layout.size(), object_layout.align()));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        panic!("deallocated_with_layout should be called instead");
+    }
+
+    fn deallocated_with_layout(
+        &self,
+        _addr: usize,
+        object_layout: Layout,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_layout.align() == ALIGN {
+            EVENTS.lock().unwrap().push((
+                "deallocated",
+                object_layout.size(),
+                object_layout.align(),
+            ));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(LayoutTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut buf = Vec::with_capacity(1);
+    buf.push(Aligned(42));
+    buf.reserve_exact(1);
+    assert_eq!(buf[0].0, 42);
+    drop(buf);
+
+    // An object smaller than its alignment leaves room for the group ID within the same alignment stride.
+    let small_layout = Layout::from_size_align(8, ALIGN).unwrap();
+    unsafe {
+        let ptr = std::alloc::alloc(small_layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % ALIGN, 0);
+        std::alloc::dealloc(ptr, small_layout);
+    }
+
+    AllocationRegistry::disable_tracking();
+
+    // The reallocation is passed through the layout-aware callbacks by default.
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("allocated", ALIGN, ALIGN),
+            ("deallocated", ALIGN, ALIGN),
+            ("allocated", 2 * ALIGN, ALIGN),
+            ("deallocated", 2 * ALIGN, ALIGN),
+            ("allocated", 8, ALIGN),
+            ("deallocated", 8, ALIGN),
+        ]
+    );
+    assert_eq!(
+        *WRAPPED_SIZES.lock().unwrap(),
+        vec![(ALIGN, 2 * ALIGN), (2 * ALIGN, 3 * ALIGN), (8, ALIGN)]
+    );
+
+    // The overhead includes the alignment padding, not just the group ID.
+    let overheads = WRAPPED_SIZES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(object_size, wrapped_size)| tracking_overhead(*object_size, *wrapped_size))
+        .collect::<Vec<_>>();
+    assert_eq!(overheads, vec![ALIGN, ALIGN, ALIGN - 8]);
+}
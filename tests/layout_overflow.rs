@@ -0,0 +1,48 @@
+//! Requesting a layout so large that adding the trailer would overflow should fail the allocation, which callers can
+//! recover from, rather than aborting the process from within the allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use tracking_allocator::Allocator;
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// The largest size a layout can have is `isize::MAX`, rounded down to its alignment, which leaves no room for the
+// trailer, whatever its size. We use an alignment of one throughout, so that this is exactly `isize::MAX`.
+const HUGE_SIZE: usize = isize::MAX as usize;
+
+#[test]
+fn alloc_returns_null() {
+    let layout = Layout::from_size_align(HUGE_SIZE, 1).unwrap();
+    unsafe {
+        assert!(ALLOCATOR.alloc(layout).is_null());
+        assert!(ALLOCATOR.alloc_zeroed(layout).is_null());
+    }
+}
+
+#[test]
+fn realloc_returns_null_and_keeps_original() {
+    let layout = Layout::from_size_align(64, 1).unwrap();
+    unsafe {
+        let ptr = ALLOCATOR.alloc(layout);
+        assert!(!ptr.is_null());
+        ptr.write_bytes(0xAB, layout.size());
+
+        assert!(ALLOCATOR.realloc(ptr, layout, HUGE_SIZE).is_null());
+        assert!(std::slice::from_raw_parts(ptr, layout.size())
+            .iter()
+            .all(|b| *b == 0xAB));
+
+        ALLOCATOR.dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn try_reserve_fails_gracefully() {
+    let mut buf = Vec::<u8>::new();
+    assert!(buf.try_reserve_exact(HUGE_SIZE).is_err());
+
+    let mut buf = vec![0u8; 16];
+    assert!(buf.try_reserve_exact(HUGE_SIZE - buf.len()).is_err());
+    assert_eq!(buf, vec![0u8; 16]);
+}
@@ -0,0 +1,70 @@
+//! A tracker should be able to register named allocation groups of its own when told about a new allocation group,
+//! including one that was itself registered by name, without deadlocking.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+static REGISTERED: Mutex<Vec<(AllocationGroupId, Option<&'static str>)>> = Mutex::new(Vec::new());
+
+struct ShadowingTracker;
+
+impl AllocationTracker for ShadowingTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn group_registered(&self, group_id: AllocationGroupId) {
+        let name = AllocationRegistry::group_name(&group_id);
+        REGISTERED.lock().unwrap().push((group_id, name));
+
+        // The tracker keeps a shadow allocation group of its own for every named allocation group.
+        if name == Some("requests") {
+            AllocationGroupToken::register_named("requests.shadow")
+                .expect("failed to register shadow allocation group");
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(ShadowingTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let requests = AllocationGroupToken::register_named("requests")
+        .expect("failed to register allocation group");
+    let shadow = AllocationGroupToken::register_named("requests.shadow")
+        .expect("failed to get shadow allocation group");
+
+    AllocationRegistry::disable_tracking();
+
+    // The allocation group is named by the time the tracker is told about it, and the shadow allocation group is only
+    // registered once.
+    assert_eq!(
+        *REGISTERED.lock().unwrap(),
+        vec![
+            (requests.id(), Some("requests")),
+            (shadow.id(), Some("requests.shadow")),
+        ]
+    );
+}
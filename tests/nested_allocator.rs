@@ -0,0 +1,62 @@
+//! Wrapping an `Allocator` in another `Allocator` should work as a plain passthrough while nothing is tracked, and, in
+//! debug builds, abort the process with an explanation as soon as an allocation, reallocation, or deallocation would be
+//! tracked twice.
+
+use std::{alloc::System, env, process::Command};
+use tracking_allocator::{AllocationRegistry, Allocator, NoopTracker};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<Allocator<System>> = Allocator::from_allocator(Allocator::system());
+
+const CHILD_ENV_VAR: &str = "TRACKING_ALLOCATOR_NESTED_CHILD";
+
+fn run_child(mode: &str) {
+    AllocationRegistry::set_global_tracker(NoopTracker)
+        .expect("no other global tracker should be set");
+
+    // The buffer is allocated before tracking is enabled, so that only growing or freeing it is ever tracked.
+    let mut buf = Vec::<u8>::with_capacity(1024);
+    AllocationRegistry::enable_tracking();
+    match mode {
+        "alloc" => drop(Vec::<u8>::with_capacity(1024)),
+        "realloc" => buf.reserve_exact(4096),
+        "dealloc" => {}
+        _ => unreachable!("unknown child mode"),
+    }
+    drop(buf);
+    AllocationRegistry::disable_tracking();
+}
+
+#[test]
+fn test() {
+    if let Some(mode) = env::var_os(CHILD_ENV_VAR) {
+        run_child(mode.to_str().expect("child mode should be valid UTF-8"));
+        return;
+    }
+
+    // Nothing is tracked in this process, so everything allocated by the test harness and by spawning the child simply
+    // passes through both layers.
+    for mode in ["alloc", "realloc", "dealloc"] {
+        let output = Command::new(env::current_exe().expect("failed to get test executable"))
+            .args(["--exact", "test", "--test-threads", "1"])
+            .env(CHILD_ENV_VAR, mode)
+            .output()
+            .expect("failed to run child process");
+
+        if cfg!(debug_assertions) {
+            assert!(
+                !output.status.success(),
+                "nested allocator should have aborted when tracking {}",
+                mode
+            );
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(
+                stderr.contains("cannot wrap another `Allocator`"),
+                "unexpected stderr: {}",
+                stderr
+            );
+        } else {
+            assert!(output.status.success());
+        }
+    }
+}
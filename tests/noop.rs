@@ -0,0 +1,25 @@
+//! The no-op tracker should exercise the tracking path without doing anything, and installing a second global tracker
+//! should fail with a useful error.
+
+use std::alloc::System;
+use tracking_allocator::{AllocationGroupToken, AllocationRegistry, Allocator, NoopTracker};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(NoopTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let buf = token.with(|| vec![0u8; 1024]);
+    drop(buf);
+
+    let err = AllocationRegistry::set_global_tracker(NoopTracker)
+        .expect_err("a global tracker should already be set");
+    assert_eq!(err.to_string(), "a global tracker has already been set");
+
+    AllocationRegistry::disable_tracking();
+}
@@ -0,0 +1,77 @@
+//! With observer accounting enabled, allocations made by the tracker itself should be counted against the observer
+//! group instead of silently disappearing, and should never be passed back to the tracker.
+
+use std::{
+    alloc::System,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// The tracker holds on to a copy of every object size it sees, which it has to allocate storage for.
+static SEEN: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+static SAW_OBSERVER_GROUP: AtomicBool = AtomicBool::new(false);
+
+struct RecordingTracker;
+
+impl AllocationTracker for RecordingTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if group_id == AllocationGroupId::OBSERVER {
+            SAW_OBSERVER_GROUP.store(true, Ordering::SeqCst);
+        }
+        SEEN.lock().unwrap().push(object_size);
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if source_group_id == AllocationGroupId::OBSERVER {
+            SAW_OBSERVER_GROUP.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(RecordingTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_observer_accounting();
+    AllocationRegistry::enable_tracking();
+
+    let mut local_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let guard = local_token.enter();
+    let values = (0..64).map(Box::new).collect::<Vec<_>>();
+    drop(guard);
+
+    AllocationRegistry::disable_tracking();
+
+    let usage = AllocationRegistry::observer_usage();
+    assert!(usage.allocations > 0);
+    assert!(usage.live_bytes() >= SEEN.lock().unwrap().capacity() * std::mem::size_of::<usize>());
+
+    // Freeing the tracker's storage, even with tracking disabled, is still counted against the observer group.
+    drop(std::mem::take(&mut *SEEN.lock().unwrap()));
+    assert_eq!(AllocationRegistry::observer_usage().live_bytes(), 0);
+
+    assert!(!SAW_OBSERVER_GROUP.load(Ordering::SeqCst));
+    drop(values);
+}
@@ -0,0 +1,88 @@
+//! Installing a tracker should call its install hook exactly once, before it sees any allocation, and without tracking
+//! anything the hook allocates.
+
+use std::{
+    alloc::System,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const SIZE: usize = 2_003;
+
+static INSTALLS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS_BEFORE_INSTALL: AtomicUsize = AtomicUsize::new(0);
+static SIZES: Mutex<Option<Vec<usize>>> = Mutex::new(None);
+
+struct InstallTracker;
+
+impl AllocationTracker for InstallTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if INSTALLS.load(Ordering::SeqCst) == 0 {
+            ALLOCATIONS_BEFORE_INSTALL.fetch_add(1, Ordering::SeqCst);
+        }
+
+        if object_size == SIZE {
+            if let Some(sizes) = SIZES.lock().unwrap().as_mut() {
+                sizes.push(object_size);
+            }
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn on_install(&self) {
+        INSTALLS.fetch_add(1, Ordering::SeqCst);
+
+        // Allocations made while setting up aren't passed back to the tracker.
+        let sizes = Vec::with_capacity(16);
+        drop(Vec::<u8>::with_capacity(SIZE));
+        *SIZES.lock().unwrap() = Some(sizes);
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    token.with(|| {
+        AllocationRegistry::set_global_tracker(InstallTracker)
+            .expect("no other global tracker should be set");
+    });
+    assert_eq!(INSTALLS.load(Ordering::SeqCst), 1);
+
+    // A failed installation never gets as far as calling the hook on the installed tracker again.
+    assert!(AllocationRegistry::set_global_tracker(InstallTracker).is_err());
+    assert_eq!(INSTALLS.load(Ordering::SeqCst), 1);
+
+    token.with(|| drop(Vec::<u8>::with_capacity(SIZE)));
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(ALLOCATIONS_BEFORE_INSTALL.load(Ordering::SeqCst), 0);
+    assert_eq!(*SIZES.lock().unwrap(), Some(vec![SIZE]));
+}
@@ -0,0 +1,83 @@
+//! Allocations made while a thread is unwinding from a panic should be attributed to the active allocation group by
+//! default, and left untracked when panic suspension is enabled.
+
+use std::{alloc::System, panic, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we ignore allocations made by the panic machinery itself.
+const UNWINDING_SIZE: usize = 12_345;
+
+static ALLOCATED: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+
+struct PanicTracker;
+
+impl AllocationTracker for PanicTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if object_size == UNWINDING_SIZE {
+            ALLOCATED.lock().unwrap().push(group_id);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+/// Allocates when dropped, which happens while unwinding.
+struct AllocateOnDrop;
+
+impl Drop for AllocateOnDrop {
+    fn drop(&mut self) {
+        drop(Vec::<u8>::with_capacity(UNWINDING_SIZE));
+    }
+}
+
+fn allocate_while_panicking(token: &mut AllocationGroupToken) {
+    let _guard = token.enter();
+    let result = panic::catch_unwind(|| {
+        let _allocate_on_drop = AllocateOnDrop;
+        panic!("unwinding");
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(PanicTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut local_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+
+    // By default, allocations made while unwinding are attributed to the active allocation group.
+    allocate_while_panicking(&mut local_token);
+    assert_eq!(*ALLOCATED.lock().unwrap(), vec![local_token.id()]);
+
+    // With panic suspension enabled, they're left untracked.
+    AllocationRegistry::enable_panic_suspension();
+    allocate_while_panicking(&mut local_token);
+    AllocationRegistry::disable_panic_suspension();
+    assert_eq!(*ALLOCATED.lock().unwrap(), vec![local_token.id()]);
+
+    AllocationRegistry::disable_tracking();
+    let _ = panic::take_hook();
+}
@@ -0,0 +1,114 @@
+//! When a buffer is grown or shrunk in a different allocation group than the one it was allocated in, the original
+//! block should be deallocated from the allocating group, and the reallocated block attributed to the active group.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself. Sizes are in bytes, and the buffers hold `Aligned` values, so that the alignment of the object is
+// larger than the alignment of the trailer.
+const INITIAL_LEN: usize = 300;
+const GROWN_LEN: usize = 700;
+const SHRUNK_LEN: usize = 100;
+const ALIGNED_SIZE: usize = std::mem::size_of::<Aligned>();
+const SIZES: [usize; 3] = [
+    INITIAL_LEN * ALIGNED_SIZE,
+    GROWN_LEN * ALIGNED_SIZE,
+    SHRUNK_LEN * ALIGNED_SIZE,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(align(64))]
+struct Aligned(usize);
+
+static ALLOCATED: Mutex<Vec<(usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+static DEALLOCATED: Mutex<Vec<(usize, AllocationGroupId, AllocationGroupId)>> =
+    Mutex::new(Vec::new());
+
+struct MarkerTracker;
+
+impl AllocationTracker for MarkerTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            ALLOCATED.lock().unwrap().push((object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            DEALLOCATED
+                .lock()
+                .unwrap()
+                .push((object_size, source_group_id, current_group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(MarkerTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut allocating_token =
+        AllocationGroupToken::register().expect("failed to register allocating group");
+    let mut growing_token =
+        AllocationGroupToken::register().expect("failed to register growing group");
+    let allocating_id = allocating_token.id();
+    let growing_id = growing_token.id();
+
+    let guard = allocating_token.enter();
+    let mut buf = Vec::with_capacity(INITIAL_LEN);
+    buf.extend((0..INITIAL_LEN).map(Aligned));
+    drop(guard);
+
+    let guard = growing_token.enter();
+    buf.reserve_exact(GROWN_LEN - INITIAL_LEN);
+    assert_eq!(buf.as_ptr() as usize % std::mem::align_of::<Aligned>(), 0);
+    buf.truncate(SHRUNK_LEN);
+    buf.shrink_to_fit();
+    assert_eq!(buf.as_ptr() as usize % std::mem::align_of::<Aligned>(), 0);
+    drop(guard);
+
+    // The contents survive the block moving around.
+    assert!(buf.iter().copied().eq((0..SHRUNK_LEN).map(Aligned)));
+    drop(buf);
+
+    AllocationRegistry::disable_tracking();
+
+    let [initial_size, grown_size, shrunk_size] = SIZES;
+    assert_eq!(
+        *ALLOCATED.lock().unwrap(),
+        vec![
+            (initial_size, allocating_id),
+            (grown_size, growing_id),
+            (shrunk_size, growing_id),
+        ]
+    );
+    assert_eq!(
+        *DEALLOCATED.lock().unwrap(),
+        vec![
+            (initial_size, allocating_id, growing_id),
+            (grown_size, growing_id, growing_id),
+            (shrunk_size, growing_id, AllocationGroupId::ROOT),
+        ]
+    );
+}
@@ -0,0 +1,109 @@
+//! Trackers that implement `reallocated` should see a single event when a tracked buffer is reallocated, carrying both
+//! the allocation group that made the original block and the allocation group active at the time of the reallocation.
+
+use std::{
+    alloc::{Layout, System},
+    sync::Mutex,
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only reallocations from this size are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const INITIAL_SIZE: usize = 30_000;
+const GROWN_SIZE: usize = 70_000;
+
+static REALLOCATED: Mutex<Vec<(usize, usize, AllocationGroupId, AllocationGroupId)>> =
+    Mutex::new(Vec::new());
+static OTHER_EVENTS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+struct ReallocTracker;
+
+impl AllocationTracker for ReallocTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_size == GROWN_SIZE {
+            OTHER_EVENTS.lock().unwrap().push(object_size);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == INITIAL_SIZE {
+            OTHER_EVENTS.lock().unwrap().push(object_size);
+        }
+    }
+
+    fn reallocated(
+        &self,
+        _old_addr: usize,
+        _new_addr: usize,
+        old_object_layout: Layout,
+        new_object_size: usize,
+        old_wrapped_size: usize,
+        new_wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        current_group_id: AllocationGroupId,
+    ) {
+        let old_object_size = old_object_layout.size();
+        assert!(old_wrapped_size >= old_object_size);
+        assert!(new_wrapped_size >= new_object_size);
+
+        if old_object_size == INITIAL_SIZE {
+            REALLOCATED.lock().unwrap().push((
+                old_object_size,
+                new_object_size,
+                source_group_id,
+                current_group_id,
+            ));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(ReallocTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut allocating_token =
+        AllocationGroupToken::register().expect("failed to register allocating group");
+    let mut growing_token =
+        AllocationGroupToken::register().expect("failed to register growing group");
+
+    let guard = allocating_token.enter();
+    let mut buf = Vec::<u8>::with_capacity(INITIAL_SIZE);
+    drop(guard);
+
+    let guard = growing_token.enter();
+    buf.reserve_exact(GROWN_SIZE);
+    drop(guard);
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *REALLOCATED.lock().unwrap(),
+        vec![(
+            INITIAL_SIZE,
+            GROWN_SIZE,
+            allocating_token.id(),
+            growing_token.id()
+        )]
+    );
+    assert!(OTHER_EVENTS.lock().unwrap().is_empty());
+}
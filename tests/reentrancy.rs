@@ -1,10 +1,14 @@
 //! The `allocated` and `deallocated` methods of the `AllocationTracker` used in this test,
 //! themselves, allocate. This test ensures that these allocations do not lead to infinite
-//! recursion.
+//! recursion, or deadlock on a lock that the tracker holds while allocating.
 
 use std::{
     alloc::System,
-    sync::atomic::{AtomicU64, Ordering},
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
 };
 use tracking_allocator::{
     AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
@@ -16,23 +20,31 @@ static ALLOCATOR: Allocator<System> = Allocator::system();
 static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
 static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
 
+// Live allocations, keyed by address. The lock is held while the map allocates, so any tracking of the map's own
+// allocations would deadlock.
+static LIVE: Mutex<Option<HashMap<usize, usize>>> = Mutex::new(None);
+
 struct AllocatingTracker;
 
 impl AllocationTracker for AllocatingTracker {
     fn allocated(
         &self,
-        _addr: usize,
-        _object_size: usize,
+        addr: usize,
+        object_size: usize,
         _wrapped_size: usize,
         _group_id: AllocationGroupId,
     ) {
         ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
         let _ = Box::new([0u64; 64]);
+        LIVE.lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(addr, object_size);
     }
 
     fn deallocated(
         &self,
-        _addr: usize,
+        addr: usize,
         _object_size: usize,
         _wrapped_size: usize,
         _source_group_id: AllocationGroupId,
@@ -40,12 +52,16 @@ impl AllocationTracker for AllocatingTracker {
     ) {
         DEALLOCATIONS.fetch_add(1, Ordering::SeqCst);
         let _ = Box::new([0u64; 64]);
+        if let Some(live) = LIVE.lock().unwrap().as_mut() {
+            live.remove(&addr);
+            live.shrink_to_fit();
+        }
     }
 }
 
 #[test]
 fn test() {
-    let _ = AllocationRegistry::set_global_tracker(AllocatingTracker)
+    AllocationRegistry::set_global_tracker(AllocatingTracker)
         .expect("no other global tracker should be set");
     AllocationRegistry::enable_tracking();
     let mut local_token =
@@ -59,12 +75,16 @@ fn test() {
     assert_eq!(0, deallocations());
 
     let alloc = Box::new(10); // allocate
+    let addr = &*alloc as *const i32 as usize;
 
     assert_eq!(1, allocations());
     assert_eq!(0, deallocations());
+    let is_live = |addr| LIVE.lock().unwrap().as_ref().unwrap().contains_key(&addr);
+    assert!(is_live(addr));
 
     drop(alloc); // deallocate
 
     assert_eq!(1, allocations());
     assert_eq!(1, deallocations());
+    assert!(!is_live(addr));
 }
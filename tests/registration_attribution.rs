@@ -0,0 +1,47 @@
+//! Registering allocation groups from within an active allocation group should not attribute any of the bookkeeping
+//! allocations made by the registry to the active allocation group.
+
+use std::alloc::System;
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, Allocator, StatsTracker,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+fn allocations(stats: &StatsTracker, group_id: &AllocationGroupId) -> usize {
+    stats
+        .snapshot()
+        .into_iter()
+        .find(|group| group.group_id == *group_id)
+        .map_or(0, |group| group.allocations)
+}
+
+#[test]
+fn test() {
+    let stats = StatsTracker::new();
+    AllocationRegistry::set_global_tracker(stats.clone())
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let registered = token.with(|| {
+        // Registering named and child groups, and naming them, records metadata in the registry, which allocates.
+        let child = AllocationGroupToken::register_with_parent(&token)
+            .expect("failed to register child allocation group");
+        AllocationRegistry::set_group_name(&child.id(), "child");
+        let named = AllocationGroupToken::register_named("registration_attribution")
+            .expect("failed to register named allocation group");
+        let plain = AllocationGroupToken::register().expect("failed to register allocation group");
+
+        [named.id(), child.id(), plain.id()]
+    });
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        AllocationRegistry::group_name(&registered[1]),
+        Some("child")
+    );
+    assert_eq!(allocations(&stats, &token.id()), 0);
+}
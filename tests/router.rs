@@ -0,0 +1,94 @@
+//! The router should pass each allocation event only to the sinks whose filters match it, with deallocations being
+//! routed by the group the allocation originated from.
+
+use std::{
+    alloc::System,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+    RouteFilter, Router,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+struct CountingSink {
+    allocations: &'static AtomicUsize,
+    deallocations: &'static AtomicUsize,
+}
+
+impl AllocationTracker for CountingSink {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        self.allocations.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        self.deallocations.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+static SMALL_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static SMALL_DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LARGE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LARGE_DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn test() {
+    let mut local_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = local_token.id();
+
+    let router = Router::new()
+        .route(
+            RouteFilter::any()
+                .with_size_range(0..=1024)
+                .with_groups([group_id]),
+            CountingSink {
+                allocations: &SMALL_ALLOCATIONS,
+                deallocations: &SMALL_DEALLOCATIONS,
+            },
+        )
+        .route(
+            RouteFilter::any()
+                .with_size_range(1_000_000..=usize::MAX)
+                .with_groups([group_id]),
+            CountingSink {
+                allocations: &LARGE_ALLOCATIONS,
+                deallocations: &LARGE_DEALLOCATIONS,
+            },
+        );
+    AllocationRegistry::set_global_tracker(router).expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let guard = local_token.enter();
+    let small = Vec::<u8>::with_capacity(16);
+    let medium = Vec::<u8>::with_capacity(4096);
+    let large = Vec::<u8>::with_capacity(1_000_000);
+    drop(guard);
+
+    assert_eq!(SMALL_ALLOCATIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(LARGE_ALLOCATIONS.load(Ordering::SeqCst), 1);
+
+    // Dropping outside of the group still routes the deallocations based on the group that allocated them.
+    drop(small);
+    drop(medium);
+    drop(large);
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(SMALL_DEALLOCATIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(LARGE_DEALLOCATIONS.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,97 @@
+//! A router should fail an allocation if any of the sinks whose filters match it want it failed, while still asking
+//! every matching sink, and never asking sinks whose filters don't match.
+
+use std::{
+    alloc::System,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+    RouteFilter, Router,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Allocations of exactly this size are failed, so that we never fail allocations made by the test harness itself.
+const FAILING_SIZE: usize = 12_347;
+
+struct FaultSink {
+    fail: bool,
+    asked: &'static AtomicUsize,
+}
+
+impl AllocationTracker for FaultSink {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn should_fail_allocation(&self, object_size: usize, _group_id: AllocationGroupId) -> bool {
+        if object_size != FAILING_SIZE {
+            return false;
+        }
+        self.asked.fetch_add(1, Ordering::SeqCst);
+        self.fail
+    }
+}
+
+static FAILING_ASKED: AtomicUsize = AtomicUsize::new(0);
+static PASSING_ASKED: AtomicUsize = AtomicUsize::new(0);
+static UNMATCHED_ASKED: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn test() {
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let other_token =
+        AllocationGroupToken::register().expect("failed to register other allocation group");
+
+    let router = Router::new()
+        .route(
+            RouteFilter::any().with_groups([token.id()]),
+            FaultSink {
+                fail: true,
+                asked: &FAILING_ASKED,
+            },
+        )
+        .route(
+            RouteFilter::any(),
+            FaultSink {
+                fail: false,
+                asked: &PASSING_ASKED,
+            },
+        )
+        .route(
+            RouteFilter::any().with_groups([other_token.id()]),
+            FaultSink {
+                fail: true,
+                asked: &UNMATCHED_ASKED,
+            },
+        );
+    AllocationRegistry::set_global_tracker(router).expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut buf = Vec::<u8>::new();
+    let failed = token.with(|| buf.try_reserve_exact(FAILING_SIZE).is_err());
+
+    AllocationRegistry::disable_tracking();
+
+    assert!(failed);
+    assert_eq!(FAILING_ASKED.load(Ordering::SeqCst), 1);
+    assert_eq!(PASSING_ASKED.load(Ordering::SeqCst), 1);
+    assert_eq!(UNMATCHED_ASKED.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,87 @@
+//! A router should report a thread exiting with an allocation group still active to the sinks whose filters match that
+//! allocation group, regardless of their size range, and to no other sinks.
+
+use std::{alloc::System, mem, sync::Mutex, thread};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+    RouteFilter, Router,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+struct UnbalancedSink {
+    groups: &'static Mutex<Vec<AllocationGroupId>>,
+}
+
+impl AllocationTracker for UnbalancedSink {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn unbalanced_thread_exit(&self, group_id: AllocationGroupId) {
+        self.groups.lock().unwrap().push(group_id);
+    }
+}
+
+static MATCHED_GROUPS: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+static SIZED_GROUPS: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+static UNMATCHED_GROUPS: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+
+#[test]
+fn test() {
+    let token = Box::leak(Box::new(
+        AllocationGroupToken::register().expect("failed to register allocation group"),
+    ));
+    let leaked_id = token.id();
+    let other_token =
+        AllocationGroupToken::register().expect("failed to register other allocation group");
+
+    let router = Router::new()
+        .route(
+            RouteFilter::any().with_groups([leaked_id]),
+            UnbalancedSink {
+                groups: &MATCHED_GROUPS,
+            },
+        )
+        .route(
+            RouteFilter::any().with_size_range(1..=1),
+            UnbalancedSink {
+                groups: &SIZED_GROUPS,
+            },
+        )
+        .route(
+            RouteFilter::any().with_groups([other_token.id()]),
+            UnbalancedSink {
+                groups: &UNMATCHED_GROUPS,
+            },
+        );
+    AllocationRegistry::set_global_tracker(router).expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    // The guard is leaked, so the allocation group is still active when the thread exits.
+    thread::spawn(move || mem::forget(token.enter()))
+        .join()
+        .unwrap();
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(*MATCHED_GROUPS.lock().unwrap(), vec![leaked_id]);
+    assert_eq!(*SIZED_GROUPS.lock().unwrap(), vec![leaked_id]);
+    assert!(UNMATCHED_GROUPS.lock().unwrap().is_empty());
+}
@@ -0,0 +1,83 @@
+//! With sampling enabled, exactly one in every N allocations on a thread should be tracked, and the deallocations of
+//! sampled allocations, and only those, should be tracked as well.
+
+use std::{
+    alloc::System,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+const SAMPLING_RATE: usize = 4;
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system().with_sampling(SAMPLING_RATE);
+
+// Only allocations of this size are counted, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_SIZE: usize = 777;
+const MARKER_ALLOCATIONS: usize = 400;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct SamplingTracker;
+
+impl AllocationTracker for SamplingTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == MARKER_SIZE {
+            DEALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[test]
+fn test() {
+    assert_eq!(ALLOCATOR.config().sampling_rate, SAMPLING_RATE);
+
+    AllocationRegistry::set_global_tracker(SamplingTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+
+    // The holder is allocated up front, so that the only allocations made in the loop are the marker allocations.
+    let mut bufs = Vec::with_capacity(MARKER_ALLOCATIONS);
+    token.with(|| {
+        for _ in 0..MARKER_ALLOCATIONS {
+            bufs.push(Vec::<u8>::with_capacity(MARKER_SIZE));
+        }
+    });
+    drop(bufs);
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        ALLOCATIONS.load(Ordering::SeqCst),
+        MARKER_ALLOCATIONS / SAMPLING_RATE
+    );
+    assert_eq!(
+        DEALLOCATIONS.load(Ordering::SeqCst),
+        MARKER_ALLOCATIONS / SAMPLING_RATE
+    );
+}
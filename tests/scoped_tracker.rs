@@ -0,0 +1,79 @@
+//! Scoped trackers should receive allocation events only while their guard is held, restoring the previously set
+//! tracker, if any, once the guard is dropped.
+
+use std::{
+    alloc::System,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracking_allocator::{AllocationGroupId, AllocationRegistry, AllocationTracker, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are counted, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const BUFFER_SIZE: usize = 60_000;
+
+struct CountingTracker(&'static AtomicUsize);
+
+impl AllocationTracker for CountingTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_size == BUFFER_SIZE {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+static OUTER: AtomicUsize = AtomicUsize::new(0);
+static INNER: AtomicUsize = AtomicUsize::new(0);
+static GLOBAL: AtomicUsize = AtomicUsize::new(0);
+
+fn allocate() {
+    drop(Vec::<u8>::with_capacity(BUFFER_SIZE));
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::enable_tracking();
+
+    let outer_guard = AllocationRegistry::set_scoped_tracker(CountingTracker(&OUTER));
+    allocate();
+
+    let inner_guard = AllocationRegistry::set_scoped_tracker(CountingTracker(&INNER));
+    allocate();
+    drop(inner_guard);
+
+    // The outer tracker is restored.
+    allocate();
+    assert_eq!(OUTER.load(Ordering::SeqCst), 2);
+    assert_eq!(INNER.load(Ordering::SeqCst), 1);
+
+    // While a scoped tracker is set, a global tracker can't be set, but once it's cleared, a global tracker can be.
+    assert!(AllocationRegistry::set_global_tracker(CountingTracker(&GLOBAL)).is_err());
+    drop(outer_guard);
+    allocate();
+    assert_eq!(OUTER.load(Ordering::SeqCst), 2);
+
+    AllocationRegistry::set_global_tracker(CountingTracker(&GLOBAL))
+        .expect("no other global tracker should be set");
+    allocate();
+    assert_eq!(GLOBAL.load(Ordering::SeqCst), 1);
+
+    AllocationRegistry::disable_tracking();
+}
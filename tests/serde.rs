@@ -0,0 +1,42 @@
+//! Allocation statistics should serialize to JSON, with allocation group IDs as plain integers.
+
+use tracking_allocator::{AllocationGroupId, GroupStats, ObserverUsage};
+
+#[test]
+fn group_stats() {
+    let stats = GroupStats {
+        group_id: AllocationGroupId::ROOT,
+        allocations: 3,
+        deallocations: 1,
+        allocated_bytes: 96,
+        deallocated_bytes: 32,
+        live_bytes: 64,
+        peak_live_bytes: 64,
+    };
+
+    assert_eq!(
+        serde_json::to_value(&stats).unwrap(),
+        serde_json::json!({
+            "group_id": 1,
+            "allocations": 3,
+            "deallocations": 1,
+            "allocated_bytes": 96,
+            "deallocated_bytes": 32,
+            "live_bytes": 64,
+            "peak_live_bytes": 64,
+        })
+    );
+}
+
+#[test]
+fn observer_usage() {
+    assert_eq!(
+        serde_json::to_value(ObserverUsage::default()).unwrap(),
+        serde_json::json!({
+            "allocations": 0,
+            "deallocations": 0,
+            "allocated_bytes": 0,
+            "deallocated_bytes": 0,
+        })
+    );
+}
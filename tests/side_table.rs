@@ -0,0 +1,117 @@
+//! In side table mode, allocations should be passed to the wrapped allocator with their requested layout, while still
+//! being attributed to the active allocation group on allocation, reallocation, and deallocation, which can also be
+//! looked up without deallocating.
+
+use std::{
+    alloc::{Layout, System},
+    sync::Mutex,
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system().with_side_table(1 << 16);
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const INITIAL_SIZE: usize = 3_001;
+const GROWN_SIZE: usize = 7_001;
+const ZEROED_SIZE: usize = 5_001;
+const SIZES: [usize; 3] = [INITIAL_SIZE, GROWN_SIZE, ZEROED_SIZE];
+
+static EVENTS: Mutex<Vec<(&'static str, usize, usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+struct SideTableTracker;
+
+impl AllocationTracker for SideTableTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("allocated", object_size, wrapped_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if SIZES.contains(&object_size) {
+            EVENTS.lock().unwrap().push((
+                "deallocated",
+                object_size,
+                wrapped_size,
+                source_group_id,
+            ));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    assert_eq!(ALLOCATOR.config().side_table_capacity, Some(1 << 16));
+
+    AllocationRegistry::set_global_tracker(SideTableTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut allocating_token =
+        AllocationGroupToken::register().expect("failed to register allocating group");
+    let mut growing_token =
+        AllocationGroupToken::register().expect("failed to register growing group");
+    let allocating_id = allocating_token.id();
+    let growing_id = growing_token.id();
+
+    let guard = allocating_token.enter();
+    let mut buf = Vec::<u8>::with_capacity(INITIAL_SIZE);
+    buf.extend((0..INITIAL_SIZE).map(|i| i as u8));
+    let zeroed_layout = Layout::from_size_align(ZEROED_SIZE, 1).unwrap();
+    let zeroed = unsafe { std::alloc::alloc_zeroed(zeroed_layout) };
+    assert!(!zeroed.is_null());
+    drop(guard);
+    assert_eq!(
+        unsafe { ALLOCATOR.group_id_for_ptr(zeroed, zeroed_layout) },
+        Some(allocating_id)
+    );
+
+    let guard = growing_token.enter();
+    buf.reserve_exact(GROWN_SIZE - INITIAL_SIZE);
+    drop(guard);
+
+    // The contents survive the block moving around, and the zeroed allocation really is zeroed.
+    assert!(buf.iter().copied().eq((0..INITIAL_SIZE).map(|i| i as u8)));
+    drop(buf);
+    unsafe {
+        assert!(std::slice::from_raw_parts(zeroed, ZEROED_SIZE)
+            .iter()
+            .all(|b| *b == 0));
+        std::alloc::dealloc(zeroed, zeroed_layout);
+    }
+
+    AllocationRegistry::disable_tracking();
+
+    // Wrapped sizes are always the same as object sizes, as nothing is added to the allocation.
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("allocated", INITIAL_SIZE, INITIAL_SIZE, allocating_id),
+            ("allocated", ZEROED_SIZE, ZEROED_SIZE, allocating_id),
+            ("deallocated", INITIAL_SIZE, INITIAL_SIZE, allocating_id),
+            ("allocated", GROWN_SIZE, GROWN_SIZE, growing_id),
+            ("deallocated", GROWN_SIZE, GROWN_SIZE, growing_id),
+            ("deallocated", ZEROED_SIZE, ZEROED_SIZE, allocating_id),
+        ]
+    );
+}
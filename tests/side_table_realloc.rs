@@ -0,0 +1,66 @@
+//! In side table mode, a reallocation that fails should leave the original allocation attributed to its allocation
+//! group, just as it leaves the allocation itself untouched.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use tracking_allocator::{AllocationGroupToken, AllocationRegistry, Allocator, NoopTracker};
+
+// Fails every reallocation to at least `FAILING_SIZE` bytes, as if memory had run out.
+struct FailingReallocator;
+
+const FAILING_SIZE: usize = 1 << 20;
+
+unsafe impl GlobalAlloc for FailingReallocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size >= FAILING_SIZE {
+            return std::ptr::null_mut();
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Allocator<FailingReallocator> =
+    Allocator::from_allocator(FailingReallocator).with_side_table(1 << 16);
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(NoopTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let layout = Layout::from_size_align(64, 1).unwrap();
+    let ptr = token.with(|| unsafe { ALLOCATOR.alloc(layout) });
+    assert!(!ptr.is_null());
+    assert_eq!(
+        unsafe { ALLOCATOR.group_id_for_ptr(ptr, layout) },
+        Some(token.id())
+    );
+
+    // The failed reallocation puts the original allocation back in the side table.
+    assert!(unsafe { ALLOCATOR.realloc(ptr, layout, FAILING_SIZE) }.is_null());
+    assert_eq!(
+        unsafe { ALLOCATOR.group_id_for_ptr(ptr, layout) },
+        Some(token.id())
+    );
+
+    // A successful reallocation moves the attribution over to the reallocated block.
+    let new_layout = Layout::from_size_align(4096, 1).unwrap();
+    let new_ptr = token.with(|| unsafe { ALLOCATOR.realloc(ptr, layout, new_layout.size()) });
+    assert!(!new_ptr.is_null());
+    assert_eq!(
+        unsafe { ALLOCATOR.group_id_for_ptr(new_ptr, new_layout) },
+        Some(token.id())
+    );
+
+    unsafe { ALLOCATOR.dealloc(new_ptr, new_layout) };
+    AllocationRegistry::disable_tracking();
+}
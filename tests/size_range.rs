@@ -0,0 +1,73 @@
+//! Allocations outside of the tracked size range configured on the allocator should never reach the tracker, while
+//! allocations at either end of the range should be tracked as usual.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system().with_tracked_size_range(64..=128);
+
+static ALLOCATED: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+static DEALLOCATED: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+struct SizeTracker;
+
+impl AllocationTracker for SizeTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if group_id != AllocationGroupId::ROOT {
+            ALLOCATED.lock().unwrap().push(object_size);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if source_group_id != AllocationGroupId::ROOT {
+            DEALLOCATED.lock().unwrap().push(object_size);
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let config = ALLOCATOR.config();
+    assert_eq!(config.tracked_size_range, 64..=128);
+    assert!(!config.tracker_installed);
+    assert!(!config.tracking_enabled);
+
+    AllocationRegistry::set_global_tracker(SizeTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let config = ALLOCATOR.config();
+    assert!(config.tracker_installed);
+    assert!(config.tracking_enabled);
+
+    let mut local_token =
+        AllocationGroupToken::register().expect("failed to register allocation group");
+    let guard = local_token.enter();
+
+    for size in [63, 64, 128, 129] {
+        let buf = Vec::<u8>::with_capacity(size);
+        drop(buf);
+    }
+
+    drop(guard);
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(*ALLOCATED.lock().unwrap(), vec![64, 128]);
+    assert_eq!(*DEALLOCATED.lock().unwrap(), vec![64, 128]);
+}
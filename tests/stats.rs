@@ -0,0 +1,94 @@
+//! The stats tracker should count allocations and deallocations against the allocation group that made them, even when
+//! they are deallocated elsewhere, and without live bytes dropping below zero for deallocations it never saw allocated.
+
+use std::alloc::System;
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+    GroupStats, StatsTracker,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+fn group_stats(stats: &StatsTracker, group_id: &AllocationGroupId) -> GroupStats {
+    stats
+        .snapshot()
+        .into_iter()
+        .find(|group| group.group_id == *group_id)
+        .expect("allocation group should have statistics")
+}
+
+#[test]
+fn test() {
+    let stats = StatsTracker::new();
+    AllocationRegistry::set_global_tracker(stats.clone())
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut allocating_token =
+        AllocationGroupToken::register().expect("failed to register allocating group");
+    let mut dropping_token =
+        AllocationGroupToken::register().expect("failed to register dropping group");
+    let allocating_id = allocating_token.id();
+    let dropping_id = dropping_token.id();
+
+    let guard = allocating_token.enter();
+    let first = Vec::<u8>::with_capacity(1024);
+    let second = Vec::<u8>::with_capacity(2048);
+    drop(guard);
+
+    let live = group_stats(&stats, &allocating_id);
+    assert_eq!(live.allocations, 2);
+    assert_eq!(live.allocated_bytes, 3072);
+    assert_eq!(live.live_bytes(), 3072);
+    assert_eq!(live.live_allocations(), 2);
+    assert_eq!(live.peak_live_bytes, 3072);
+
+    // Deallocations are counted against the allocating group, not the dropping group.
+    let guard = dropping_token.enter();
+    drop(first);
+    drop(second);
+    drop(guard);
+
+    // A smaller allocation afterwards doesn't move the peak.
+    let guard = allocating_token.enter();
+    let third = Vec::<u8>::with_capacity(512);
+    drop(guard);
+    drop(third);
+
+    AllocationRegistry::disable_tracking();
+
+    let freed = group_stats(&stats, &allocating_id);
+    assert_eq!(freed.deallocations, 3);
+    assert_eq!(freed.deallocated_bytes, 3584);
+    assert_eq!(freed.peak_live_bytes, 3072);
+    assert_eq!(freed.live_bytes(), 0);
+    assert_eq!(freed.live_allocations(), 0);
+    assert!(stats
+        .snapshot()
+        .iter()
+        .all(|group| group.group_id != dropping_id));
+
+    // Snapshots are ordered by group ID.
+    let snapshot = stats.snapshot();
+    assert!(snapshot
+        .windows(2)
+        .all(|pair| pair[0].group_id < pair[1].group_id));
+}
+
+#[test]
+fn deallocation_before_allocation() {
+    let stats = StatsTracker::new();
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    // The allocation being deallocated was made before this tracker was installed, so it never saw it.
+    stats.deallocated(0x1000, 100, 100, group_id, group_id);
+    stats.allocated(0x2000, 10, 10, group_id);
+
+    let group = group_stats(&stats, &group_id);
+    assert_eq!(group.deallocated_bytes, 100);
+    assert_eq!(group.allocated_bytes, 10);
+    assert_eq!(group.live_bytes(), 10);
+    assert_eq!(group.peak_live_bytes, 10);
+}
@@ -0,0 +1,85 @@
+//! Diffing two stats snapshots should give the change in live bytes of every allocation group over the code region
+//! between them, including allocation groups that only appear in one of the snapshots.
+
+use std::alloc::System;
+use tracking_allocator::{
+    diff_live_bytes, AllocationGroupId, AllocationGroupToken, AllocationRegistry, Allocator,
+    GroupStats, StatsTracker,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+fn group_stats(
+    group_id: AllocationGroupId,
+    allocated_bytes: usize,
+    deallocated_bytes: usize,
+) -> GroupStats {
+    GroupStats {
+        group_id,
+        allocations: 1,
+        deallocations: 0,
+        allocated_bytes,
+        deallocated_bytes,
+        live_bytes: allocated_bytes - deallocated_bytes,
+        peak_live_bytes: allocated_bytes,
+    }
+}
+
+#[test]
+fn test_region() {
+    let stats = StatsTracker::new();
+    AllocationRegistry::set_global_tracker(stats.clone())
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut freeing_token =
+        AllocationGroupToken::register().expect("failed to register freeing group");
+    let mut leaking_token =
+        AllocationGroupToken::register().expect("failed to register leaking group");
+
+    let guard = freeing_token.enter();
+    let freed = Vec::<u8>::with_capacity(1024);
+    drop(guard);
+
+    let before = stats.snapshot();
+
+    // The freeing group only shows up in the first snapshot, and the leaking group only in the second.
+    drop(freed);
+    let guard = leaking_token.enter();
+    let leaked = Vec::<u8>::with_capacity(2048);
+    drop(guard);
+
+    let after = stats.snapshot();
+
+    AllocationRegistry::disable_tracking();
+
+    let deltas = diff_live_bytes(&before, &after);
+    assert!(deltas.contains(&(freeing_token.id(), -1024)));
+    assert!(deltas.contains(&(leaking_token.id(), 2048)));
+
+    drop(leaked);
+}
+
+#[test]
+fn test_groups_in_one_snapshot() {
+    let (first, second, third) = (
+        AllocationGroupId::ROOT,
+        AllocationGroupToken::register()
+            .expect("failed to register allocation group")
+            .id(),
+        AllocationGroupToken::register()
+            .expect("failed to register allocation group")
+            .id(),
+    );
+
+    let before = vec![group_stats(first, 100, 0), group_stats(second, 300, 100)];
+    let after = vec![group_stats(first, 100, 0), group_stats(third, 50, 0)];
+
+    // Unchanged allocation groups are left out, and the rest are ordered by group ID.
+    assert_eq!(
+        diff_live_bytes(&before, &after),
+        vec![(second, -200), (third, 50)]
+    );
+    assert!(diff_live_bytes(&after, &after).is_empty());
+}
@@ -0,0 +1,49 @@
+//! Taking a stats snapshot from within an allocation group that has never allocated should not deadlock, even when the
+//! allocations made by the snapshot itself would be counted in the shard that the snapshot is reading from.
+
+use std::{alloc::System, sync::mpsc, thread, time::Duration};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+    StatsTracker,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+#[test]
+fn test() {
+    let stats = StatsTracker::new();
+    AllocationRegistry::set_global_tracker(stats.clone())
+        .expect("no other global tracker should be set");
+
+    // Allocation groups are spread across 16 shards by group ID, and shards are read in order, so an allocation group
+    // whose ID is a multiple of 16 lives in the first shard read by the snapshot.
+    let token = loop {
+        let token = AllocationGroupToken::register().expect("failed to register allocation group");
+        if token.id().as_usize().get().is_multiple_of(16) {
+            break token;
+        }
+    };
+
+    // The snapshot only allocates while reading a shard when the shard isn't empty.
+    let neighbor_id = AllocationGroupId::from_raw(token.id().as_usize().get() + 16)
+        .expect("group ID should be valid");
+    stats.allocated(0x1000, 8, 8, neighbor_id);
+
+    AllocationRegistry::enable_tracking();
+
+    let (tx, rx) = mpsc::channel();
+    let snapshot_stats = stats.clone();
+    thread::spawn(move || {
+        let snapshot = token.with(|| snapshot_stats.snapshot());
+        tx.send(snapshot).expect("receiver should still be waiting");
+    });
+
+    let snapshot = rx
+        .recv_timeout(Duration::from_secs(10))
+        .expect("snapshot should not deadlock");
+
+    AllocationRegistry::disable_tracking();
+
+    assert!(snapshot.iter().any(|group| group.group_id == neighbor_id));
+}
@@ -0,0 +1,126 @@
+//! Suspending an allocation guard should attribute allocations to the root allocation group until it is resumed, and
+//! should hand the previously active allocation group back once the suspended guard is dropped. Suspending tracking
+//! for a closure should leave its allocations untracked, and restore the active allocation group even on panic.
+
+use std::{alloc::System, panic, sync::Mutex};
+use tracking_allocator::{
+    suspend_tracking, AllocationGroupId, AllocationGroupToken, AllocationRegistry,
+    AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations with a size in this range are recorded, so that we can ignore any incidental allocations made by
+// the test harness itself.
+const MARKER_SIZES: std::ops::Range<usize> = 10_000..10_100;
+
+static ALLOCATED: Mutex<Vec<(usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+struct MarkerTracker;
+
+impl AllocationTracker for MarkerTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if MARKER_SIZES.contains(&object_size) {
+            ALLOCATED.lock().unwrap().push((object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+fn allocate_marker(offset: usize) {
+    drop(Vec::<u8>::with_capacity(MARKER_SIZES.start + offset));
+}
+
+fn take_allocated() -> Vec<(usize, AllocationGroupId)> {
+    std::mem::take(&mut *ALLOCATED.lock().unwrap())
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(MarkerTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut outer_token = AllocationGroupToken::register().expect("failed to register outer group");
+    let mut inner_token = AllocationGroupToken::register().expect("failed to register inner group");
+    let mut nested_token =
+        AllocationGroupToken::register().expect("failed to register nested group");
+    let outer_id = outer_token.id();
+    let inner_id = inner_token.id();
+    let nested_id = nested_token.id();
+
+    let outer_guard = outer_token.enter();
+
+    // Suspending and resuming puts the inner group back in exactly the same spot.
+    let inner_guard = inner_token.enter();
+    allocate_marker(0);
+    let suspended = inner_guard.suspend();
+    allocate_marker(1);
+    let nested_guard = nested_token.enter();
+    allocate_marker(2);
+    drop(nested_guard);
+    allocate_marker(3);
+    let inner_guard = suspended.resume();
+    allocate_marker(4);
+
+    // Dropping the suspended guard, rather than resuming it, restores the outer group.
+    let suspended = inner_guard.suspend();
+    allocate_marker(5);
+    drop(suspended);
+    allocate_marker(6);
+
+    // Suspending tracking leaves allocations untracked, and restores the outer group afterwards, even on panic.
+    assert_eq!(
+        suspend_tracking(|| {
+            allocate_marker(7);
+            42
+        }),
+        42
+    );
+    allocate_marker(8);
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(|| {
+        suspend_tracking(|| {
+            allocate_marker(9);
+            panic!("suspended");
+        })
+    });
+    let _ = panic::take_hook();
+    assert!(result.is_err());
+    allocate_marker(10);
+
+    drop(outer_guard);
+    AllocationRegistry::disable_tracking();
+
+    let start = MARKER_SIZES.start;
+    assert_eq!(
+        take_allocated(),
+        vec![
+            (start, inner_id),
+            (start + 1, AllocationGroupId::ROOT),
+            (start + 2, nested_id),
+            (start + 3, AllocationGroupId::ROOT),
+            (start + 4, inner_id),
+            (start + 5, AllocationGroupId::ROOT),
+            (start + 6, outer_id),
+            (start + 8, outer_id),
+            (start + 10, outer_id),
+        ]
+    );
+}
@@ -0,0 +1,111 @@
+//! Trackers should be told which thread made each allocation and deallocation, including the name of the thread.
+
+use std::{alloc::System, sync::Mutex, thread};
+use tracking_allocator::{AllocationGroupId, AllocationRegistry, AllocationTracker, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const MARKER_SIZE: usize = 6_543;
+
+static EVENTS: Mutex<Vec<(&'static str, Option<String>)>> = Mutex::new(Vec::new());
+
+struct ThreadTracker;
+
+impl AllocationTracker for ThreadTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        panic!("allocated_on_thread should be called instead");
+    }
+
+    fn allocated_on_thread(
+        &self,
+        _addr: usize,
+        object_layout: std::alloc::Layout,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+        thread: &thread::Thread,
+    ) {
+        if object_layout.size() == MARKER_SIZE {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("allocated", thread.name().map(String::from)));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        panic!("deallocated_on_thread should be called instead");
+    }
+
+    fn deallocated_on_thread(
+        &self,
+        _addr: usize,
+        object_layout: std::alloc::Layout,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+        thread: &thread::Thread,
+    ) {
+        if object_layout.size() == MARKER_SIZE {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(("deallocated", thread.name().map(String::from)));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(ThreadTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let buf = thread::Builder::new()
+        .name("allocating".into())
+        .spawn(|| Vec::<u8>::with_capacity(MARKER_SIZE))
+        .unwrap()
+        .join()
+        .unwrap();
+    thread::Builder::new()
+        .name("deallocating".into())
+        .spawn(move || drop(buf))
+        .unwrap()
+        .join()
+        .unwrap();
+
+    // Zeroed allocations go through the same path by default.
+    let zeroed = thread::Builder::new()
+        .name("zeroing".into())
+        .spawn(|| vec![0u8; MARKER_SIZE])
+        .unwrap()
+        .join()
+        .unwrap();
+
+    AllocationRegistry::disable_tracking();
+    drop(zeroed);
+
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("allocated", Some("allocating".to_string())),
+            ("deallocated", Some("deallocating".to_string())),
+            ("allocated", Some("zeroing".to_string())),
+        ]
+    );
+}
@@ -0,0 +1,76 @@
+//! A thread that exits while an allocation group is still active, such as when a guard is leaked, should be reported to
+//! the tracker, while a thread that exits every allocation group it entered should not. The reported group ID should be
+//! formattable from within the tracker, even though the thread is already tearing down its thread locals.
+
+use std::{alloc::System, mem, sync::Mutex, thread};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+static UNBALANCED_GROUPS: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+static UNBALANCED_LABELS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct UnbalancedTracker;
+
+impl AllocationTracker for UnbalancedTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn unbalanced_thread_exit(&self, group_id: AllocationGroupId) {
+        UNBALANCED_GROUPS.lock().unwrap().push(group_id);
+        UNBALANCED_LABELS.lock().unwrap().push(group_id.to_string());
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(UnbalancedTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    // Balanced: the guard is dropped before the thread exits.
+    thread::spawn(|| {
+        let mut token =
+            AllocationGroupToken::register().expect("failed to register allocation group");
+        let _guard = token.enter();
+        let _ = Box::new(42u64);
+    })
+    .join()
+    .unwrap();
+    assert!(UNBALANCED_GROUPS.lock().unwrap().is_empty());
+
+    // Unbalanced: the guard is leaked, so the allocation group is still active when the thread exits.
+    let leaked_id = thread::spawn(|| {
+        let token = Box::leak(Box::new(
+            AllocationGroupToken::register().expect("failed to register allocation group"),
+        ));
+        AllocationRegistry::set_group_name(&token.id(), "leaked");
+        mem::forget(token.enter());
+        token.id()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(*UNBALANCED_GROUPS.lock().unwrap(), vec![leaked_id]);
+    assert_eq!(*UNBALANCED_LABELS.lock().unwrap(), vec!["leaked"]);
+
+    AllocationRegistry::disable_tracking();
+}
@@ -0,0 +1,40 @@
+//! Pins the thread-safety contract of tokens and guards: tokens can be moved and shared across threads, while guards
+//! can only be shared, as they must be exited on the thread that entered them.
+
+use tracking_allocator::{AllocationGroupToken, AllocationGuard, SuspendedAllocationGuard};
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn test_token_is_send_and_sync() {
+    assert_send::<AllocationGroupToken>();
+    assert_sync::<AllocationGroupToken>();
+}
+
+#[test]
+fn test_guards_are_sync() {
+    // Sharing a guard only gives access to the ID of its allocation group, which is safe from any thread. The guards
+    // are `!Send`, which is covered by the `compile_fail` doctests on their private marker fields.
+    assert_sync::<AllocationGuard<'static>>();
+    assert_sync::<SuspendedAllocationGuard<'static>>();
+}
+
+#[test]
+fn test_token_moves_across_threads() {
+    let mut token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let id = token.id();
+
+    let guard = token.enter();
+    assert_eq!(guard.id(), id);
+    guard.exit();
+
+    let token = std::thread::spawn(move || {
+        let mut token = token;
+        token.enter().exit();
+        token
+    })
+    .join()
+    .expect("thread should not panic");
+    assert_eq!(token.id(), id);
+}
@@ -0,0 +1,121 @@
+//! The threshold tracker should call its callback once each time an allocation group crosses the limit, only re-arming
+//! once the live bytes of the allocation group drop below the hysteresis, and pass every event on to the inner tracker.
+//! Deallocations that the tracker never saw allocated mustn't push live bytes below zero.
+
+use std::{
+    alloc::System,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+    NoopTracker, ThresholdTracker,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+const LIMIT: usize = 64 * 1024;
+const HYSTERESIS: usize = 16 * 1024;
+const CHUNK: usize = 20 * 1024;
+
+static ALARMS: Mutex<Vec<(AllocationGroupId, usize)>> = Mutex::new(Vec::new());
+static INNER_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static UNSEEN_ALARMS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingTracker;
+
+impl AllocationTracker for CountingTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_size == CHUNK {
+            INNER_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+fn alarms() -> Vec<(AllocationGroupId, usize)> {
+    ALARMS.lock().unwrap().clone()
+}
+
+#[test]
+fn test() {
+    let tracker = ThresholdTracker::new(CountingTracker, LIMIT, |group_id, live_bytes| {
+        // Allocating in the callback isn't attributed to the allocation group that crossed the limit.
+        ALARMS.lock().unwrap().push((group_id, live_bytes));
+    })
+    .with_hysteresis(HYSTERESIS);
+    AllocationRegistry::set_global_tracker(tracker).expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let mut token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let id = token.id();
+
+    let guard = token.enter();
+    let mut chunks = (0..3)
+        .map(|_| Vec::<u8>::with_capacity(CHUNK))
+        .collect::<Vec<_>>();
+    drop(guard);
+    assert!(alarms().is_empty());
+
+    // Crossing the limit raises a single alarm, however far past it the allocation group goes.
+    let guard = token.enter();
+    chunks.push(Vec::<u8>::with_capacity(CHUNK));
+    chunks.push(Vec::<u8>::with_capacity(CHUNK));
+    drop(guard);
+    assert_eq!(alarms().len(), 1);
+    assert_eq!(alarms()[0].0, id);
+    assert!(alarms()[0].1 >= LIMIT);
+
+    // Dropping back below the limit, but not below the hysteresis, doesn't re-arm the alarm.
+    chunks.truncate(3);
+    let guard = token.enter();
+    chunks.push(Vec::<u8>::with_capacity(CHUNK));
+    drop(guard);
+    assert_eq!(alarms().len(), 1);
+
+    // Dropping below the hysteresis does.
+    chunks.truncate(2);
+    let guard = token.enter();
+    chunks.push(Vec::<u8>::with_capacity(CHUNK));
+    chunks.push(Vec::<u8>::with_capacity(CHUNK));
+    drop(guard);
+    assert_eq!(alarms().len(), 2);
+    assert_eq!(alarms()[1].0, id);
+
+    drop(chunks);
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(INNER_ALLOCATIONS.load(Ordering::SeqCst), 8);
+}
+
+#[test]
+fn deallocation_before_allocation() {
+    let tracker = ThresholdTracker::new(NoopTracker, LIMIT, |_, _| {
+        UNSEEN_ALARMS.fetch_add(1, Ordering::SeqCst);
+    });
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let id = token.id();
+
+    // The allocation being deallocated was made before this tracker was installed, so it never saw it.
+    tracker.deallocated(0x1000, CHUNK, CHUNK, id, id);
+    tracker.allocated(0x2000, 1, 1, id);
+    assert_eq!(UNSEEN_ALARMS.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,90 @@
+//! Futures instrumented with an allocation group should have their allocations attributed to it across `.await`
+//! points, while allocations made by other tasks in between polls should not be.
+
+use std::{alloc::System, sync::Mutex};
+use tokio::sync::oneshot;
+use tracking_allocator::{
+    instrument_alloc, AllocationGroupId, AllocationGroupToken, AllocationRegistry,
+    AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of these sizes are recorded, so that we can ignore any incidental allocations made by the runtime or
+// the test harness itself.
+const BEFORE_AWAIT_SIZE: usize = 70_001;
+const AFTER_AWAIT_SIZE: usize = 70_002;
+const OTHER_TASK_SIZE: usize = 70_003;
+
+static ALLOCATED: Mutex<Vec<(usize, AllocationGroupId)>> = Mutex::new(Vec::new());
+
+struct MarkerTracker;
+
+impl AllocationTracker for MarkerTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if [BEFORE_AWAIT_SIZE, AFTER_AWAIT_SIZE, OTHER_TASK_SIZE].contains(&object_size) {
+            ALLOCATED.lock().unwrap().push((object_size, group_id));
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(MarkerTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let group_id = token.id();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("failed to build runtime");
+    runtime.block_on(async {
+        let (tx, rx) = oneshot::channel::<()>();
+
+        let instrumented = tokio::spawn(instrument_alloc(token, async move {
+            drop(Vec::<u8>::with_capacity(BEFORE_AWAIT_SIZE));
+            rx.await.expect("sender should not be dropped");
+            drop(Vec::<u8>::with_capacity(AFTER_AWAIT_SIZE));
+        }));
+
+        let other = tokio::spawn(async move {
+            drop(Vec::<u8>::with_capacity(OTHER_TASK_SIZE));
+            tx.send(()).expect("receiver should not be dropped");
+        });
+
+        instrumented
+            .await
+            .expect("instrumented task should not panic");
+        other.await.expect("other task should not panic");
+    });
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *ALLOCATED.lock().unwrap(),
+        vec![
+            (BEFORE_AWAIT_SIZE, group_id),
+            (OTHER_TASK_SIZE, AllocationGroupId::ROOT),
+            (AFTER_AWAIT_SIZE, group_id),
+        ]
+    );
+}
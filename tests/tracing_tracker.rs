@@ -0,0 +1,87 @@
+//! The tracing tracker should emit an event for every allocation and deallocation, without recursing into itself when
+//! the subscriber allocates while handling those events.
+
+use std::{alloc::System, sync::Mutex};
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, layer::SubscriberExt, Layer, Registry};
+use tracking_allocator::{AllocationGroupToken, AllocationRegistry, Allocator, TracingTracker};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only events for allocations of this size are recorded, so that we can ignore any incidental allocations made by the
+// test harness itself.
+const MARKER_SIZE: u64 = 5_678;
+
+static EVENTS: Mutex<Vec<(String, u64)>> = Mutex::new(Vec::new());
+
+#[derive(Default)]
+struct EventVisitor {
+    message: String,
+    object_size: u64,
+    group_id: u64,
+    source_group_id: u64,
+}
+
+impl Visit for EventVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "object_size" => self.object_size = value,
+            "group_id" => self.group_id = value,
+            "source_group_id" => self.source_group_id = value,
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            // The subscriber allocates while handling the event, which must not recurse into the tracker.
+            self.message = format!("{:?}", value);
+        }
+    }
+}
+
+struct RecordingLayer;
+
+impl<S: Subscriber> Layer<S> for RecordingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != "tracking_allocator" {
+            return;
+        }
+
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+        if visitor.object_size == MARKER_SIZE {
+            let group_id = visitor.group_id.max(visitor.source_group_id);
+            EVENTS.lock().unwrap().push((visitor.message, group_id));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    tracing::subscriber::set_global_default(Registry::default().with(RecordingLayer))
+        .expect("no other global subscriber should be set");
+
+    AllocationRegistry::set_global_tracker(TracingTracker::new())
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    let buf = token.with(|| Vec::<u8>::with_capacity(MARKER_SIZE as usize));
+    drop(buf);
+
+    AllocationRegistry::disable_tracking();
+
+    let group_id = token.id().as_usize().get() as u64;
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("allocated".to_string(), group_id),
+            ("deallocated".to_string(), group_id),
+        ]
+    );
+}
@@ -0,0 +1,96 @@
+//! With the `track-lifetime` feature, deallocations should be reported along with the number of allocations tracked
+//! since the allocation was made, and reallocations should keep the age of the original allocation.
+
+use std::sync::Mutex;
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<std::alloc::System> = Allocator::system();
+
+// Only deallocations of these sizes are recorded, so that we can ignore any incidental allocations made by the test
+// harness itself.
+const INITIAL_SIZE: usize = 2_003;
+const GROWN_SIZE: usize = 8_003;
+const SHORT_LIVED_SIZE: usize = 3_001;
+const SIZES: [usize; 3] = [INITIAL_SIZE, GROWN_SIZE, SHORT_LIVED_SIZE];
+
+const INTERVENING_ALLOCATIONS: u64 = 100;
+
+static AGES: Mutex<Vec<(usize, AllocationGroupId, u64)>> = Mutex::new(Vec::new());
+
+struct AgeTracker;
+
+impl AllocationTracker for AgeTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated_with_age(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        source_group_id: AllocationGroupId,
+        age: u64,
+    ) {
+        if SIZES.contains(&object_size) {
+            AGES.lock()
+                .unwrap()
+                .push((object_size, source_group_id, age));
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(AgeTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    token.with(|| {
+        let mut long_lived = Vec::<u8>::with_capacity(INITIAL_SIZE);
+
+        let intervening = (0..INTERVENING_ALLOCATIONS)
+            .map(Box::new)
+            .collect::<Vec<_>>();
+        drop(intervening);
+
+        // Growing the allocation shouldn't reset its age.
+        long_lived.reserve_exact(GROWN_SIZE);
+        drop(long_lived);
+
+        drop(Vec::<u8>::with_capacity(SHORT_LIVED_SIZE));
+    });
+
+    AllocationRegistry::disable_tracking();
+
+    let ages = AGES.lock().unwrap();
+    assert_eq!(ages.len(), 2, "unexpected deallocations: {ages:?}");
+
+    let (object_size, source_group_id, age) = ages[0];
+    assert_eq!(object_size, GROWN_SIZE);
+    assert_eq!(source_group_id, token.id());
+    assert!(age >= INTERVENING_ALLOCATIONS, "age was only {}", age);
+
+    let (object_size, source_group_id, age) = ages[1];
+    assert_eq!(object_size, SHORT_LIVED_SIZE);
+    assert_eq!(source_group_id, token.id());
+    assert!(age < INTERVENING_ALLOCATIONS, "age was {}", age);
+}
@@ -0,0 +1,65 @@
+//! Allocations made while tracking is disabled should never reach the tracker, not even when they are deallocated after
+//! tracking has been enabled again.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{AllocationGroupId, AllocationRegistry, AllocationTracker, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+// Only allocations of this size are recorded, so that we can ignore any incidental allocations made by the test harness
+// itself.
+const BUFFER_SIZE: usize = 50_000;
+
+static EVENTS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+struct EventTracker;
+
+impl AllocationTracker for EventTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+        if object_size == BUFFER_SIZE {
+            EVENTS.lock().unwrap().push("allocated");
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        _source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == BUFFER_SIZE {
+            EVENTS.lock().unwrap().push("deallocated");
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(EventTracker)
+        .expect("no other global tracker should be set");
+    assert!(!AllocationRegistry::is_tracking_enabled());
+
+    // Allocated while disabled, deallocated while enabled.
+    let untracked = Vec::<u8>::with_capacity(BUFFER_SIZE);
+    AllocationRegistry::enable_tracking();
+    assert!(AllocationRegistry::is_tracking_enabled());
+    drop(untracked);
+    assert!(EVENTS.lock().unwrap().is_empty());
+
+    let tracked = Vec::<u8>::with_capacity(BUFFER_SIZE);
+    drop(tracked);
+
+    AllocationRegistry::disable_tracking();
+    assert!(!AllocationRegistry::is_tracking_enabled());
+
+    assert_eq!(*EVENTS.lock().unwrap(), vec!["allocated", "deallocated"]);
+}
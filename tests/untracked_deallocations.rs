@@ -0,0 +1,61 @@
+//! Deallocations of untracked allocations should only reach the tracker once untracked deallocations are enabled, and
+//! should be reported with the sentinel untracked group ID.
+
+use std::{alloc::System, sync::Mutex};
+use tracking_allocator::{AllocationGroupId, AllocationRegistry, AllocationTracker, Allocator};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system().with_tracked_size_range(0..=1024);
+
+// Allocations of this size are outside of the tracked size range, so they are never tracked.
+const UNTRACKED_SIZE: usize = 4096;
+
+static DEALLOCATED: Mutex<Vec<AllocationGroupId>> = Mutex::new(Vec::new());
+
+struct UntrackedTracker;
+
+impl AllocationTracker for UntrackedTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        _group_id: AllocationGroupId,
+    ) {
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if object_size == UNTRACKED_SIZE {
+            DEALLOCATED.lock().unwrap().push(source_group_id);
+        }
+    }
+}
+
+#[test]
+fn test() {
+    AllocationRegistry::set_global_tracker(UntrackedTracker)
+        .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    drop(Vec::<u8>::with_capacity(UNTRACKED_SIZE));
+    assert!(DEALLOCATED.lock().unwrap().is_empty());
+
+    AllocationRegistry::enable_untracked_deallocations();
+    drop(Vec::<u8>::with_capacity(UNTRACKED_SIZE));
+    AllocationRegistry::disable_untracked_deallocations();
+
+    drop(Vec::<u8>::with_capacity(UNTRACKED_SIZE));
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(
+        *DEALLOCATED.lock().unwrap(),
+        vec![AllocationGroupId::UNTRACKED]
+    );
+}
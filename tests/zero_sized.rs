@@ -0,0 +1,76 @@
+//! Zero-sized values and empty collections never reach the allocator, so they should never produce allocation events,
+//! no matter how much they're churned.
+
+use std::{
+    alloc::System,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracking_allocator::{
+    AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker, Allocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<System> = Allocator::system();
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingTracker {
+    group_id: AllocationGroupId,
+}
+
+impl AllocationTracker for CountingTracker {
+    fn allocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
+    ) {
+        if group_id == self.group_id {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn deallocated(
+        &self,
+        _addr: usize,
+        _object_size: usize,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
+    ) {
+        if source_group_id == self.group_id {
+            DEALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let token = AllocationGroupToken::register().expect("failed to register allocation group");
+    AllocationRegistry::set_global_tracker(CountingTracker {
+        group_id: token.id(),
+    })
+    .expect("no other global tracker should be set");
+    AllocationRegistry::enable_tracking();
+
+    token.with(|| {
+        for _ in 0..1_000 {
+            let unit = Box::new(());
+            let mut units = vec![(); 64];
+            units.push(());
+            let mut empty = Vec::<u64>::new();
+            empty.shrink_to_fit();
+            drop((unit, units, empty, String::new()));
+        }
+    });
+
+    // A non-zero-sized allocation is still tracked as usual, and balanced with its deallocation.
+    token.with(|| drop(Box::new(42u64)));
+
+    AllocationRegistry::disable_tracking();
+
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(DEALLOCATIONS.load(Ordering::SeqCst), 1);
+}